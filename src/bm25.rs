@@ -0,0 +1,318 @@
+// src/bm25.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use crate::chunk::{note_of, rebase_id};
+use crate::store::index_path;
+
+/// Term frequency saturation of BM25.
+const K1: f64 = 1.2;
+/// Document length normalization of BM25.
+const B: f64 = 0.75;
+
+/// BM25 inverted index over the chunks of a vault's notes, kept next to its
+/// vector store and keyed by the same chunk ids, so that lexical and semantic
+/// results can be fused per note.
+///
+/// Chunks are numbered, and the postings refer to them by number; the chunk
+/// ids are stored once, in `docs`. The index is written with bincode.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Bm25Index {
+    /// Chunk id and number of terms by chunk number; `None` for removed chunks
+    /// until the index is compacted on save.
+    docs: Vec<Option<(String, u32)>>,
+    /// For each term, the numbers of the chunks it occurs in and how often.
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    /// Chunk numbers by chunk id, rebuilt on load.
+    #[serde(skip)]
+    numbers: HashMap<String, u32>,
+}
+
+impl Bm25Index {
+    /// Loads the index of `vault_name`, or returns `None` if it was never built.
+    pub fn load(vault_name: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let path = index_path(vault_name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut index: Bm25Index = bincode::deserialize_from(BufReader::new(File::open(path)?))
+            .map_err(|e| format!("Failed to load word index: {}", e))?;
+        index.numbers = index
+            .docs
+            .iter()
+            .enumerate()
+            .filter_map(|(number, doc)| Some((doc.as_ref()?.0.clone(), number as u32)))
+            .collect();
+        Ok(Some(index))
+    }
+
+    /// Compacts the index and writes it to disk.
+    pub fn save(&mut self, vault_name: &str) -> Result<(), Box<dyn Error>> {
+        self.compact();
+        let file = BufWriter::new(File::create(index_path(vault_name)?)?);
+        bincode::serialize_into(file, self)
+            .map_err(|e| format!("Failed to save word index: {}", e))?;
+        Ok(())
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.numbers.contains_key(id)
+    }
+
+    /// Indexes `text` under `id`, replacing what was indexed under it before.
+    pub fn insert(&mut self, id: &str, text: &str) {
+        if self.contains(id) {
+            self.remove(|doc| doc == id);
+        }
+        let number = self.docs.len() as u32;
+        let terms = tokenize(text);
+        self.docs.push(Some((id.to_string(), terms.len() as u32)));
+        self.numbers.insert(id.to_string(), number);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for term in terms {
+            *counts.entry(term).or_default() += 1;
+        }
+        for (term, count) in counts {
+            self.postings.entry(term).or_default().push((number, count));
+        }
+    }
+
+    /// Drops every chunk whose id matches `drop`.
+    pub fn remove(&mut self, mut drop: impl FnMut(&str) -> bool) {
+        let mut removed = HashSet::new();
+        for (number, doc) in self.docs.iter_mut().enumerate() {
+            if doc.as_ref().is_some_and(|(id, _)| drop(id))
+                && let Some((id, _)) = doc.take()
+            {
+                self.numbers.remove(&id);
+                removed.insert(number as u32);
+            }
+        }
+        if removed.is_empty() {
+            return;
+        }
+        self.postings.retain(|_, docs| {
+            docs.retain(|(number, _)| !removed.contains(number));
+            !docs.is_empty()
+        });
+    }
+
+    /// Drops the chunks of the note at `relpath`.
+    pub fn remove_note(&mut self, relpath: &str) {
+        self.remove(|id| note_of(id) == relpath);
+    }
+
+    /// Moves the chunks of the note at `old_relpath` to `new_relpath`.
+    pub fn rename_note(&mut self, old_relpath: &str, new_relpath: &str) {
+        for (id, _) in self.docs.iter_mut().flatten() {
+            if note_of(id) == old_relpath {
+                let number = self.numbers.remove(id.as_str());
+                *id = rebase_id(id, new_relpath);
+                if let Some(number) = number {
+                    self.numbers.insert(id.clone(), number);
+                }
+            }
+        }
+    }
+
+    /// Renumbers the chunks so that removed ones take no space.
+    fn compact(&mut self) {
+        if self.docs.iter().all(Option::is_some) {
+            return;
+        }
+        let mut renumbered = vec![u32::MAX; self.docs.len()];
+        let mut docs = Vec::with_capacity(self.numbers.len());
+        for (number, doc) in std::mem::take(&mut self.docs).into_iter().enumerate() {
+            if let Some(doc) = doc {
+                renumbered[number] = docs.len() as u32;
+                docs.push(Some(doc));
+            }
+        }
+        for postings in self.postings.values_mut() {
+            for (number, _) in postings.iter_mut() {
+                *number = renumbered[*number as usize];
+            }
+        }
+        self.numbers = docs
+            .iter()
+            .enumerate()
+            .filter_map(|(number, doc)| Some((doc.as_ref()?.0.clone(), number as u32)))
+            .collect();
+        self.docs = docs;
+    }
+
+    /// Scores every chunk containing a term of `query` with BM25, best first.
+    pub fn search(&self, query: &str) -> Vec<(String, f64)> {
+        let lengths: Vec<u32> = self.docs.iter().flatten().map(|(_, len)| *len).collect();
+        if lengths.is_empty() {
+            return Vec::new();
+        }
+        let total = lengths.len() as f64;
+        let average = lengths.iter().map(|&len| f64::from(len)).sum::<f64>() / total;
+
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        for term in &terms {
+            let Some(docs) = self.postings.get(term) else {
+                continue;
+            };
+            let found = docs.len() as f64;
+            let idf = (1.0 + (total - found + 0.5) / (found + 0.5)).ln();
+            for &(number, count) in docs {
+                let Some(Some((_, len))) = self.docs.get(number as usize) else {
+                    continue;
+                };
+                let count = f64::from(count);
+                let norm = K1 * (1.0 - B + B * f64::from(*len) / average.max(1.0));
+                *scores.entry(number).or_default() += idf * count * (K1 + 1.0) / (count + norm);
+            }
+        }
+
+        let mut scores: Vec<(String, f64)> = scores
+            .into_iter()
+            .filter_map(|(number, score)| {
+                let (id, _) = self.docs.get(number as usize)?.as_ref()?;
+                Some((id.clone(), score))
+            })
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores
+    }
+}
+
+/// Splits `text` into lowercase terms. Identifiers such as `PROJ-1234`,
+/// `parse_chunk_id` or `v1.2` are kept whole, so they match exactly, and are
+/// also split at their `-`, `_` and `.` so that a part of one matches too.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let words = text
+        .split(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+        .map(|word| word.trim_matches(|c| "-_.".contains(c)))
+        .filter(|word| !word.is_empty());
+    for word in words {
+        let word = word.to_lowercase();
+        let parts: Vec<&str> = word
+            .split(['-', '_', '.'])
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.len() > 1 {
+            terms.extend(parts.iter().map(|part| part.to_string()));
+        }
+        terms.push(word);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(docs: &[(&str, &str)]) -> Bm25Index {
+        let mut index = Bm25Index::default();
+        for (id, text) in docs {
+            index.insert(id, text);
+        }
+        index
+    }
+
+    fn ids(results: &[(String, f64)]) -> Vec<&str> {
+        results.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenize_lowercases_and_splits_at_punctuation() {
+        assert_eq!(tokenize("Hello, World!"), ["hello", "world"]);
+        assert_eq!(tokenize("(see: notes)"), ["see", "notes"]);
+        assert!(tokenize(" -- ... ").is_empty());
+    }
+
+    #[test]
+    fn tokenize_keeps_identifiers_whole_and_in_parts() {
+        assert_eq!(tokenize("PROJ-1234"), ["proj", "1234", "proj-1234"]);
+        assert_eq!(
+            tokenize("parse_chunk_id()"),
+            ["parse", "chunk", "id", "parse_chunk_id"]
+        );
+        assert_eq!(tokenize("v1.2."), ["v1", "2", "v1.2"]);
+    }
+
+    #[test]
+    fn search_ranks_rarer_terms_higher() {
+        let index = index(&[
+            ("a.md#chunk-0", "the release of the parser"),
+            ("b.md#chunk-0", "the release of the cache"),
+            ("c.md#chunk-0", "the roadmap"),
+        ]);
+        // "parser" occurs once and "release" twice, so a.md wins on "parser".
+        let results = index.search("release parser");
+        assert_eq!(ids(&results), ["a.md#chunk-0", "b.md#chunk-0"]);
+        assert!(results[0].1 > results[1].1);
+        assert!(index.search("missing").is_empty());
+    }
+
+    #[test]
+    fn search_prefers_shorter_chunks_and_more_occurrences() {
+        let index = index(&[
+            ("long.md#chunk-0", "cache one two three four five six seven"),
+            ("short.md#chunk-0", "cache here"),
+            (
+                "many.md#chunk-0",
+                "cache cache cache one two three four five",
+            ),
+        ]);
+        let results = index.search("cache");
+        assert_eq!(results[0].0, "many.md#chunk-0");
+        assert_eq!(results[1].0, "short.md#chunk-0");
+        assert_eq!(results[2].0, "long.md#chunk-0");
+    }
+
+    #[test]
+    fn search_matches_identifiers_exactly_and_by_part() {
+        let index = index(&[
+            ("a.md#chunk-0", "Fixed in PROJ-1234"),
+            ("b.md#chunk-0", "See PROJ-99 and issue 1234"),
+        ]);
+        assert_eq!(index.search("proj-1234")[0].0, "a.md#chunk-0");
+        assert_eq!(ids(&index.search("PROJ")).len(), 2);
+    }
+
+    #[test]
+    fn remove_rename_and_compact_keep_the_index_consistent() {
+        let mut index = index(&[
+            ("a.md#chunk-0", "alpha shared"),
+            ("a.md#chunk-1", "alpha more"),
+            ("b.md#chunk-0", "beta shared"),
+        ]);
+        index.remove_note("a.md");
+        assert!(!index.contains("a.md#chunk-0"));
+        assert!(index.search("alpha").is_empty());
+        assert_eq!(ids(&index.search("shared")), ["b.md#chunk-0"]);
+
+        index.rename_note("b.md", "dir/c.md");
+        index.compact();
+        assert_eq!(index.docs.len(), 1);
+        assert!(index.contains("dir/c.md#chunk-0"));
+        assert_eq!(ids(&index.search("beta")), ["dir/c.md#chunk-0"]);
+
+        index.insert("dir/c.md#chunk-0", "gamma");
+        assert!(index.search("beta").is_empty());
+        assert_eq!(ids(&index.search("gamma")), ["dir/c.md#chunk-0"]);
+    }
+
+    #[test]
+    fn index_survives_a_bincode_round_trip() {
+        let mut index = index(&[("a.md#chunk-0", "alpha"), ("b.md#chunk-0", "beta")]);
+        index.remove_note("a.md");
+        index.compact();
+        let bytes = bincode::serialize(&index).unwrap();
+        let loaded: Bm25Index = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(ids(&loaded.search("beta")), ["b.md#chunk-0"]);
+    }
+}
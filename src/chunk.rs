@@ -0,0 +1,282 @@
+// src/chunk.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::frontmatter;
+
+/// Separator between the ids of a note and of its chunks in the vector store.
+const CHUNK_SEPARATOR: &str = "#chunk-";
+
+/// Where a chunk lies in its note.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ChunkMeta {
+    /// Headings enclosing the chunk, outermost first, joined with " > ".
+    pub heading: String,
+    /// First line of the chunk, 1-based, counting the frontmatter.
+    pub start: usize,
+    /// Last line of the chunk.
+    pub end: usize,
+}
+
+/// A piece of a note that gets its own embedding.
+pub struct Chunk {
+    pub meta: ChunkMeta,
+    /// Text to embed: the heading path followed by the words of the chunk.
+    pub text: String,
+}
+
+/// Returns the vector store id of chunk `index` of the note at `relpath`.
+pub fn chunk_id(relpath: &str, index: usize) -> String {
+    format!("{}{}{}", relpath, CHUNK_SEPARATOR, index)
+}
+
+/// Splits a chunk id into the note's relpath and the chunk index. Ids of
+/// stores built before notes were chunked are plain relpaths and yield `None`.
+pub fn parse_chunk_id(id: &str) -> Option<(&str, usize)> {
+    let (relpath, index) = id.rsplit_once(CHUNK_SEPARATOR)?;
+    Some((relpath, index.parse().ok()?))
+}
+
+/// Returns the id that `id`, a vector of some note, gets when the note moves to `relpath`.
+pub fn rebase_id(id: &str, relpath: &str) -> String {
+    match parse_chunk_id(id) {
+        Some((_, index)) => chunk_id(relpath, index),
+        None => relpath.to_string(),
+    }
+}
+
+/// Returns the relpath of the note a vector store id belongs to.
+pub fn note_of(id: &str) -> &str {
+    parse_chunk_id(id).map_or(id, |(relpath, _)| relpath)
+}
+
+/// Splits a note into chunks for embedding.
+///
+/// The body is first split into sections at its Markdown headings (outside code
+/// fences); each section is then cut into windows of at most `size` words, with
+/// consecutive windows of a section sharing `overlap` words. Words stand in for
+/// model tokens, which keeps chunking independent of the tokenizer.
+pub fn chunk_note(content: &str, size: usize, overlap: usize) -> Vec<Chunk> {
+    let size = size.max(1);
+    let step = size - overlap.min(size - 1);
+
+    let (_, body) = frontmatter::split(content);
+    let offset = content[..content.len() - body.len()].lines().count();
+
+    let mut chunks = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut section = Section::default();
+    let mut fence = Fence::default();
+    for (i, line) in body.lines().enumerate() {
+        let number = offset + i + 1;
+        if !fence.fenced(line)
+            && let Some((level, title)) = heading(line)
+        {
+            section.split(size, step, &mut chunks);
+            headings.retain(|(l, _)| *l < level);
+            headings.push((level, title.to_string()));
+            section.heading = headings
+                .iter()
+                .map(|(_, title)| title.as_str())
+                .collect::<Vec<_>>()
+                .join(" > ");
+            // The heading already starts the text of every chunk.
+            section.line = Some(number);
+            continue;
+        }
+        section
+            .words
+            .extend(line.split_whitespace().map(|word| (word, number)));
+    }
+    section.split(size, step, &mut chunks);
+    chunks
+}
+
+/// Follows the code fences of a note line by line.
+#[derive(Default)]
+pub struct Fence {
+    /// Marker of the open fence.
+    open: Option<&'static str>,
+}
+
+impl Fence {
+    /// Takes the next line and returns true if it is part of a code block,
+    /// including the fence lines themselves.
+    pub fn fenced(&mut self, line: &str) -> bool {
+        let was_open = self.open.is_some();
+        let trimmed = line.trim_start();
+        for marker in ["```", "~~~"] {
+            if trimmed.starts_with(marker) {
+                match self.open {
+                    Some(open) if open == marker => self.open = None,
+                    None => self.open = Some(marker),
+                    _ => {}
+                }
+            }
+        }
+        was_open || self.open.is_some()
+    }
+}
+
+/// The words of the section being collected, with their line numbers.
+#[derive(Default)]
+struct Section<'a> {
+    heading: String,
+    /// Line of the heading that opened the section, where its first chunk starts.
+    line: Option<usize>,
+    words: Vec<(&'a str, usize)>,
+}
+
+impl Section<'_> {
+    /// Cuts the section into windows of `size` words, `step` words apart,
+    /// appends them to `chunks` and clears the words.
+    fn split(&mut self, size: usize, step: usize, chunks: &mut Vec<Chunk>) {
+        let mut start = 0;
+        while start < self.words.len() {
+            let end = (start + size).min(self.words.len());
+            let window = &self.words[start..end];
+            let words: Vec<&str> = window.iter().map(|(word, _)| *word).collect();
+            let text = if self.heading.is_empty() {
+                words.join(" ")
+            } else {
+                format!("{}\n{}", self.heading, words.join(" "))
+            };
+            chunks.push(Chunk {
+                meta: ChunkMeta {
+                    heading: self.heading.clone(),
+                    start: match self.line {
+                        Some(line) if start == 0 => line,
+                        _ => window[0].1,
+                    },
+                    end: window[window.len() - 1].1,
+                },
+                text,
+            });
+            if end == self.words.len() {
+                break;
+            }
+            start += step;
+        }
+        self.words.clear();
+        self.line = None;
+    }
+}
+
+/// Parses an ATX heading such as `## Setup` into its level and title.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !(rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t')) {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(chunks: &[Chunk]) -> Vec<&str> {
+        chunks.iter().map(|chunk| chunk.text.as_str()).collect()
+    }
+
+    fn lines(chunks: &[Chunk]) -> Vec<(usize, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| (chunk.meta.start, chunk.meta.end))
+            .collect()
+    }
+
+    #[test]
+    fn chunks_follow_headings_and_their_nesting() {
+        let chunks = chunk_note("intro\n# A\none\n## B\ntwo\n# C\nthree\n", 10, 0);
+        assert_eq!(
+            texts(&chunks),
+            ["intro", "A\none", "A > B\ntwo", "C\nthree"]
+        );
+        assert_eq!(lines(&chunks), [(1, 1), (2, 3), (4, 5), (6, 7)]);
+    }
+
+    #[test]
+    fn hash_lines_in_code_blocks_are_not_headings() {
+        let content = "# A\n```sh\n# comment\n```\n~~~\n## not\n```\n~~~\nend\n";
+        let chunks = chunk_note(content, 20, 0);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].meta.heading, "A");
+        assert_eq!(
+            chunks[0].text,
+            "A\n```sh # comment ``` ~~~ ## not ``` ~~~ end"
+        );
+        assert_eq!(lines(&chunks), [(1, 9)]);
+    }
+
+    #[test]
+    fn sections_are_cut_into_overlapping_windows() {
+        let chunks = chunk_note("a b c d e f g", 3, 1);
+        assert_eq!(texts(&chunks), ["a b c", "c d e", "e f g"]);
+        let chunks = chunk_note("a b c d", 3, 0);
+        assert_eq!(texts(&chunks), ["a b c", "d"]);
+    }
+
+    #[test]
+    fn overlap_of_size_or_more_still_advances() {
+        let chunks = chunk_note("a b c d", 2, 2);
+        assert_eq!(texts(&chunks), ["a b", "b c", "c d"]);
+        let chunks = chunk_note("a b c d", 2, 10);
+        assert_eq!(texts(&chunks), ["a b", "b c", "c d"]);
+        let chunks = chunk_note("a b", 0, 0);
+        assert_eq!(texts(&chunks), ["a", "b"]);
+    }
+
+    #[test]
+    fn line_numbers_count_the_frontmatter() {
+        let content = "---\ntitle: T\ntags: [x]\n---\nfirst\n\n# H\nsecond\n";
+        let chunks = chunk_note(content, 10, 0);
+        assert_eq!(texts(&chunks), ["first", "H\nsecond"]);
+        assert_eq!(lines(&chunks), [(5, 5), (7, 8)]);
+    }
+
+    #[test]
+    fn headings_without_text_make_no_chunk() {
+        let chunks = chunk_note("# A\n## B\n\n## C\ntext\n# D\n", 10, 0);
+        assert_eq!(texts(&chunks), ["A > C\ntext"]);
+        assert_eq!(lines(&chunks), [(4, 5)]);
+        assert!(chunk_note("# Only\n", 10, 0).is_empty());
+        assert!(chunk_note("", 10, 0).is_empty());
+    }
+
+    #[test]
+    fn later_windows_start_at_their_first_word() {
+        let chunks = chunk_note("# H\na b\nc d\n", 2, 0);
+        assert_eq!(lines(&chunks), [(1, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn chunk_ids_round_trip() {
+        assert_eq!(chunk_id("dir/a.md", 3), "dir/a.md#chunk-3");
+        assert_eq!(parse_chunk_id("dir/a.md#chunk-3"), Some(("dir/a.md", 3)));
+        assert_eq!(note_of("dir/a.md#chunk-3"), "dir/a.md");
+        assert_eq!(rebase_id("dir/a.md#chunk-3", "b.md"), "b.md#chunk-3");
+    }
+
+    #[test]
+    fn ids_without_a_chunk_suffix_are_whole_notes() {
+        assert_eq!(parse_chunk_id("dir/a.md"), None);
+        assert_eq!(parse_chunk_id("a.md#chunk-x"), None);
+        assert_eq!(note_of("dir/a.md"), "dir/a.md");
+        assert_eq!(note_of("a.md#chunk-"), "a.md#chunk-");
+        assert_eq!(rebase_id("dir/a.md", "b.md"), "b.md");
+    }
+
+    #[test]
+    fn headings_need_a_space_after_the_hashes() {
+        assert_eq!(heading("## Setup ##"), Some((2, "Setup")));
+        assert_eq!(heading("#"), Some((1, "")));
+        assert_eq!(heading("#tag"), None);
+        assert_eq!(heading("####### seven"), None);
+    }
+}
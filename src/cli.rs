@@ -0,0 +1,411 @@
+// src/cli.rs
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::completions::{CompletionKind, CompletionShell};
+use crate::crud::IfExists;
+use crate::daily::PeriodicOptions;
+use crate::mv::Destination;
+use crate::related::WriteTarget;
+use crate::search::SearchMode;
+use crate::vault::ALL_VAULTS;
+
+/// Exit code used when a command fails at runtime.
+/// Usage errors (unknown commands, bad flags) are reported by clap with exit code 2.
+pub const EXIT_FAILURE: i32 = 1;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables quiet mode for the rest of the process.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Returns true if informational output should be suppressed.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Strips the optional leading '@' from a vault argument, so both `@work` and `work` are accepted.
+fn parse_vault(arg: &str) -> Result<String, String> {
+    let name = arg.trim_start_matches('@');
+    if name.is_empty() {
+        return Err("vault name must not be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Like `parse_vault`, but for arguments naming a single vault, where `@*` is rejected.
+fn parse_single_vault(arg: &str) -> Result<String, String> {
+    let name = parse_vault(arg)?;
+    if name == ALL_VAULTS {
+        return Err("'@*' stands for every vault; name a single vault".to_string());
+    }
+    Ok(name)
+}
+
+/// Parses a `YYYY-MM-DD` date.
+fn parse_date(arg: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(arg, "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a date in YYYY-MM-DD format", arg))
+}
+
+/// A CLI tool for PKM.
+///
+/// Running `ncy` without a command opens the note picker for the default vault,
+/// or for every vault with `-a`.
+#[derive(Parser)]
+#[command(name = "ncy", version, about, long_about = None)]
+pub struct Cli {
+    /// Vault to operate on, overriding the default vault.
+    #[arg(long, global = true, value_name = "VAULT", value_parser = parse_vault)]
+    pub vault: Option<String>,
+
+    /// Configuration directory, overriding NOTEMANCY_CONF_DIR and the XDG default.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Open the note picker over the notes of every vault; the same as `--vault @*`.
+    #[arg(short, long)]
+    pub all_vaults: bool,
+
+    /// Suppress informational output; only results and errors are printed.
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Set up notemancy: runs a setup wizard on first use, otherwise opens config.yaml.
+    Init {
+        /// Skip the wizard and edit config.yaml in the editor directly.
+        #[arg(long)]
+        edit: bool,
+    },
+
+    /// Inspect and validate the configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Set the default vault.
+    Set {
+        /// Name of the vault as defined in config.yaml.
+        #[arg(value_parser = parse_single_vault)]
+        vault: String,
+    },
+
+    /// Create a new note and open it in the editor.
+    ///
+    /// Without --title the title and project are prompted for. A body piped on
+    /// stdin is written to the note, e.g. `echo "..." | ncy n -t "Quick"`.
+    #[command(visible_alias = "n")]
+    New {
+        /// Title of the note.
+        #[arg(short, long)]
+        title: Option<String>,
+        /// Project folder inside the vault, e.g. `work/meetings`.
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Tag to add to the frontmatter; may be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Render the note from a template; without a name, pick one interactively.
+        #[arg(
+            short = 'T',
+            long,
+            value_name = "NAME",
+            num_args = 0..=1,
+            default_missing_value = ""
+        )]
+        template: Option<String>,
+        /// What to do if a note with the same file name exists; asks by default.
+        #[arg(long, value_name = "ACTION")]
+        if_exists: Option<IfExists>,
+        /// Print the path of the created note without opening the editor.
+        #[arg(long)]
+        no_edit: bool,
+    },
+
+    /// Create or open today's daily note.
+    Today {
+        #[command(flatten)]
+        options: PeriodicArgs,
+    },
+
+    /// Create or open yesterday's daily note.
+    Yesterday {
+        #[command(flatten)]
+        options: PeriodicArgs,
+    },
+
+    /// Create or open the note for the current week.
+    Week {
+        #[command(flatten)]
+        options: PeriodicArgs,
+    },
+
+    /// Create or open the daily note for a given date.
+    Journal {
+        /// Date of the note, in YYYY-MM-DD format.
+        #[arg(value_parser = parse_date)]
+        date: NaiveDate,
+        #[command(flatten)]
+        options: PeriodicArgs,
+    },
+
+    /// Select a note and print its absolute path, or open it with `--open`.
+    #[command(short_flag = 'e')]
+    Edit {
+        /// Vault to select from, written as `@vault`; `@*` selects from every vault.
+        #[arg(value_name = "@VAULT", value_parser = parse_vault)]
+        vault: Option<String>,
+        /// Print the path of the selected note (the default unless --open is given).
+        #[arg(long)]
+        print_path: bool,
+        /// Open the selected note in the editor.
+        #[arg(short, long)]
+        open: bool,
+        /// Select with an external fzf instead of the built-in picker.
+        #[arg(long)]
+        fzf: bool,
+    },
+
+    /// Rename a note or move it to another project or vault, rewriting links to it.
+    ///
+    /// The destination is a new title, a project folder ending in `/` (`/` alone
+    /// is the vault root), or `@vault`.
+    Mv {
+        /// Note to move: a path relative to the vault, a file name or a title.
+        note: String,
+        /// New title, `project/` or `@vault`.
+        #[arg(value_parser = Destination::parse)]
+        destination: Destination,
+    },
+
+    /// Move a note to the vault's trash; it can be restored with `ncy trash restore`.
+    Rm {
+        /// Note to delete: a path relative to the vault, a file name or a title.
+        /// Without it, the note is chosen in the picker.
+        note: Option<String>,
+    },
+
+    /// Move a note into the vault's `archive/` folder, rewriting links to it.
+    Archive {
+        /// Note to archive: a path relative to the vault, a file name or a title.
+        /// Without it, the note is chosen in the picker.
+        note: Option<String>,
+    },
+
+    /// List, restore or permanently delete notes in the vault's trash.
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+
+    /// List the notes opened most recently, newest first.
+    Recent {
+        /// Number of notes to list.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Search the text of every note and open the chosen line in the editor.
+    ///
+    /// When stdout is not a terminal the matching lines are printed as
+    /// `path:line: text` instead.
+    Grep {
+        /// Initial search query.
+        #[arg(default_value = "")]
+        query: String,
+        /// Print the matching lines instead of opening the picker.
+        #[arg(short, long)]
+        list: bool,
+    },
+
+    /// Find the notes that best match a question, by meaning and by its words.
+    ///
+    /// Run `ncy vectorize` first; it builds both the vector store and the word
+    /// index that search uses. The two rankings are fused unless --mode picks
+    /// one. When stdout is not a terminal the results are
+    /// printed as `score  path:line  heading` instead of opening the picker.
+    #[command(visible_alias = "semsearch")]
+    Ask {
+        /// Question or text to search for.
+        query: String,
+        /// Number of notes to return; defaults to embedding.top_k in config.yaml, or 10.
+        #[arg(short = 'k', long, value_name = "N")]
+        top_k: Option<usize>,
+        /// Print the results instead of opening the picker.
+        #[arg(short, long)]
+        list: bool,
+        /// Rank by the words of the query, by meaning, or by both.
+        #[arg(short, long, value_enum, default_value_t = SearchMode::Hybrid)]
+        mode: SearchMode,
+    },
+
+    /// List the notes closest in meaning to a note, using the vectorized vault.
+    ///
+    /// Run `ncy vectorize` first. When stdout is not a terminal the results are
+    /// printed as `score  path:line  heading` instead of opening the picker.
+    Related {
+        /// Note to start from: a path relative to the vault, a file name or a title.
+        /// Without it, the note is chosen in the picker.
+        note: Option<String>,
+        /// Number of notes to return; defaults to embedding.top_k in config.yaml, or 10.
+        #[arg(short = 'k', long, value_name = "N")]
+        top_k: Option<usize>,
+        /// Print the results instead of opening the picker.
+        #[arg(short, long)]
+        list: bool,
+        /// Link the related notes from the note, in a `## Related` section or a
+        /// `related` frontmatter field.
+        #[arg(short, long, value_name = "WHERE")]
+        write: Option<WriteTarget>,
+    },
+
+    /// Manage the vaults defined in config.yaml.
+    Vault {
+        #[command(subcommand)]
+        command: VaultCommand,
+    },
+
+    /// Print the directory of a vault.
+    Cd {
+        /// Name of the vault as defined in config.yaml.
+        #[arg(value_parser = parse_single_vault)]
+        vault: String,
+    },
+
+    /// Generate embeddings for the new and changed notes of a vault.
+    Vectorize {
+        /// Vault to vectorize; defaults to the default vault.
+        #[arg(value_parser = parse_vault)]
+        vault: Option<String>,
+        /// Re-embed every note instead of only new and changed ones.
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Upload every note in the vault given with `--vault`, or the default vault, to the
+    /// configured publish_url.
+    Publish,
+
+    /// Print a shell completion script.
+    ///
+    /// For example: `source <(ncy completions bash)`.
+    Completions {
+        /// Shell to generate the script for.
+        shell: CompletionShell,
+    },
+
+    /// List completion candidates; used by the completion scripts.
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Kind of candidates to list.
+        kind: CompletionKind,
+    },
+}
+
+/// Flags shared by the daily and weekly note commands.
+#[derive(Args)]
+pub struct PeriodicArgs {
+    /// Copy unfinished tasks from the previous note into a new one,
+    /// overriding `daily.rollover` in config.yaml.
+    #[arg(long, overrides_with = "no_rollover")]
+    pub rollover: bool,
+    /// Do not copy unfinished tasks, overriding `daily.rollover`.
+    #[arg(long)]
+    pub no_rollover: bool,
+    /// Print the path of the note without opening the editor.
+    #[arg(long)]
+    pub no_edit: bool,
+}
+
+impl From<PeriodicArgs> for PeriodicOptions {
+    fn from(args: PeriodicArgs) -> Self {
+        PeriodicOptions {
+            rollover: match (args.rollover, args.no_rollover) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            no_edit: args.no_edit,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum VaultCommand {
+    /// List configured vaults; the active vault is marked with '*'.
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Add an existing directory as a vault.
+    Add {
+        /// Name of the new vault.
+        name: String,
+        /// Directory holding the vault's notes.
+        path: PathBuf,
+    },
+
+    /// Remove a vault from config.yaml; its notes are left on disk.
+    #[command(visible_alias = "rm")]
+    Remove {
+        /// Name of the vault to remove.
+        #[arg(value_parser = parse_vault)]
+        name: String,
+    },
+
+    /// Rename a vault.
+    Rename {
+        /// Current name of the vault.
+        #[arg(value_parser = parse_vault)]
+        old: String,
+        /// New name of the vault.
+        new: String,
+    },
+
+    /// Show details of a vault; defaults to the active vault.
+    Show {
+        /// Name of the vault to show.
+        #[arg(value_parser = parse_vault)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// List deleted notes with their id, deletion time and original path.
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Move a deleted note back to its original path.
+    Restore {
+        /// Trash id or original path of the note.
+        id: String,
+    },
+
+    /// Permanently delete every note in the trash.
+    Empty {
+        /// Do not ask for confirmation.
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Validate config.yaml and report problems with their line and column.
+    Check,
+
+    /// Print the resolved configuration, data and cache directories.
+    Path,
+}
@@ -0,0 +1,179 @@
+// src/completions.rs
+
+use crate::notes::list_notes;
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::{Shell, generate};
+use std::error::Error;
+use std::io;
+
+use crate::cli::Cli;
+use crate::vault::{VaultContext, configured_vaults};
+
+/// Shells for which `ncy completions` can emit a script.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// What the hidden `ncy __complete` entry point should list.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CompletionKind {
+    /// Vault names from config.yaml.
+    Vaults,
+    /// Note relpaths in the selected vault.
+    Notes,
+}
+
+// The static scripts generated by clap know every subcommand and flag but cannot
+// know vault names or notes. Each hook below wraps the generated completion and
+// asks `ncy __complete` for vault names where a vault argument is expected, and
+// for the notes of the selected vault where a note is.
+
+const BASH_HOOK: &str = r#"
+_ncy_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "--vault" ]]; then
+        COMPREPLY=( $(compgen -W "$(ncy __complete vaults 2>/dev/null)" -- "$cur") )
+        return 0
+    fi
+    # The words before the cursor, without `--vault <name>`.
+    local args=() vault=() i
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        if [[ "${COMP_WORDS[i]}" == "--vault" ]]; then
+            vault=( --vault "${COMP_WORDS[i+1]}" )
+            (( i++ ))
+        else
+            args+=( "${COMP_WORDS[i]}" )
+        fi
+    done
+    if [[ ${#args[@]} -eq 1 ]]; then
+        case "${args[0]}" in
+            cd|set|vectorize)
+                COMPREPLY=( $(compgen -W "$(ncy __complete vaults 2>/dev/null)" -- "$cur") )
+                return 0
+                ;;
+            mv|rm|archive|related)
+                local IFS=$'\n'
+                COMPREPLY=( $(compgen -W "$(ncy "${vault[@]}" __complete notes 2>/dev/null)" -- "$cur") )
+                return 0
+                ;;
+            edit|-e)
+                COMPREPLY=( $(compgen -P @ -W "$(ncy __complete vaults 2>/dev/null)" -- "${cur#@}") )
+                return 0
+                ;;
+        esac
+    fi
+    _ncy "$@"
+}
+complete -F _ncy_dynamic -o bashdefault -o default ncy
+"#;
+
+const ZSH_HOOK: &str = r#"
+_ncy_dynamic() {
+    if [[ "${words[CURRENT-1]}" == "--vault" ]]; then
+        compadd -- ${(f)"$(ncy __complete vaults 2>/dev/null)"}
+        return
+    fi
+    # The words before the cursor, without `--vault <name>`.
+    local -a args vault
+    local i
+    for (( i = 2; i < CURRENT; i++ )); do
+        if [[ "${words[i]}" == "--vault" ]]; then
+            vault=(--vault "${words[i+1]}")
+            (( i++ ))
+        else
+            args+=("${words[i]}")
+        fi
+    done
+    if (( ${#args} == 1 )); then
+        case "${args[1]}" in
+            cd|set|vectorize)
+                compadd -- ${(f)"$(ncy __complete vaults 2>/dev/null)"}
+                return
+                ;;
+            mv|rm|archive|related)
+                compadd -- ${(f)"$(ncy "${vault[@]}" __complete notes 2>/dev/null)"}
+                return
+                ;;
+            edit|-e)
+                compadd -P @ -- ${(f)"$(ncy __complete vaults 2>/dev/null)"}
+                return
+                ;;
+        esac
+    fi
+    _ncy "$@"
+}
+compdef _ncy_dynamic ncy
+"#;
+
+const FISH_HOOK: &str = r#"
+complete -c ncy -l vault -f -a "(ncy __complete vaults 2>/dev/null)"
+complete -c ncy -n "__fish_seen_subcommand_from cd set vectorize" -f -a "(ncy __complete vaults 2>/dev/null)"
+complete -c ncy -n "__fish_seen_subcommand_from edit -e" -f -a "(ncy __complete vaults 2>/dev/null | string replace -r '^' '@')"
+
+# The words before the cursor, without `--vault <name>`.
+function __ncy_args
+    set -l tokens (commandline -opc)
+    while set -l i (contains -i -- --vault $tokens)
+        set -e tokens[$i]
+        set -q tokens[$i]; and set -e tokens[$i]
+    end
+    printf '%s\n' $tokens
+end
+
+function __ncy_note_expected
+    set -l args (__ncy_args)
+    test (count $args) -eq 2; and contains -- $args[2] mv rm archive related
+end
+
+function __ncy_notes
+    set -l tokens (commandline -opc)
+    set -l vault
+    if set -l i (contains -i -- --vault $tokens); and test $i -lt (count $tokens)
+        set vault --vault $tokens[(math $i + 1)]
+    end
+    ncy $vault __complete notes 2>/dev/null
+end
+
+complete -c ncy -n __ncy_note_expected -f -a "(__ncy_notes)"
+"#;
+
+/// Writes the completion script for `shell` to stdout.
+pub fn print_completions(shell: CompletionShell) {
+    let mut cmd = Cli::command();
+    let mut stdout = io::stdout();
+    let (target, hook) = match shell {
+        CompletionShell::Bash => (Shell::Bash, BASH_HOOK),
+        CompletionShell::Zsh => (Shell::Zsh, ZSH_HOOK),
+        CompletionShell::Fish => (Shell::Fish, FISH_HOOK),
+    };
+    generate(target, &mut cmd, "ncy", &mut stdout);
+    print!("{}", hook);
+}
+
+/// Prints completion candidates of the given kind, one per line.
+///
+/// Completion must never spam the terminal, so callers are expected to
+/// discard errors rather than report them.
+pub fn print_candidates(
+    kind: CompletionKind,
+    vault: Option<&VaultContext>,
+) -> Result<(), Box<dyn Error>> {
+    match kind {
+        CompletionKind::Vaults => {
+            for (name, _) in configured_vaults()? {
+                println!("{}", name);
+            }
+        }
+        CompletionKind::Notes => {
+            let vault = vault.ok_or("No vault to list notes from")?;
+            for note in list_notes(&vault.name)? {
+                println!("{}", note.relpath);
+            }
+        }
+    }
+    Ok(())
+}
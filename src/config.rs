@@ -1,55 +1,74 @@
+use crate::paths::config_dir;
+use crate::schema::report_issues;
+use crate::setup::run_setup_wizard;
+use inquire::Confirm;
+use notemancy_core::config::read_config;
 use std::env;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Initializes the notemancy configuration.
 ///
-/// This function checks for the configuration directory specified by the
-/// `NOTEMANCY_CONF_DIR` environment variable. If the directory does not exist,
+/// This function checks for the configuration directory (see `paths::config_dir`).
+/// If the directory does not exist,
 /// it is created. Then it checks for the existence of `config.yaml` within that
-/// directory; if the file does not exist, an empty `config.yaml` is created.
-/// Finally, it opens the configuration file in the default editor as configured
-/// in the shell (using the `EDITOR` environment variable, defaulting to "vi").
-pub fn init_config() -> Result<(), Box<dyn Error>> {
-    // Retrieve the configuration directory from the environment variable.
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")
-        .map_err(|_| "Environment variable NOTEMANCY_CONF_DIR is not set")?;
-    let conf_path = Path::new(&conf_dir);
+/// directory; if the file does not exist, the interactive setup wizard writes it,
+/// unless `edit` is set, in which case an empty `config.yaml` is created.
+/// Otherwise, it opens the configuration file in the default editor (see
+/// `editor_command`). When the editor exits the file is validated, and if it has
+/// errors the user is offered to reopen it.
+pub fn init_config(edit: bool) -> Result<(), Box<dyn Error>> {
+    // Retrieve the configuration directory.
+    let conf_dir = config_dir()?;
+    let conf_path = conf_dir.as_path();
 
     // Ensure the configuration directory exists.
     if !conf_path.exists() {
-        fs::create_dir_all(&conf_path)?;
+        fs::create_dir_all(conf_path)?;
     }
 
     // Define the path to config.yaml.
     let config_file = conf_path.join("config.yaml");
 
-    // If config.yaml does not exist, create an empty file.
+    // If config.yaml does not exist, run the setup wizard or create an empty file.
     if !config_file.exists() {
+        if !edit {
+            return run_setup_wizard(&config_file);
+        }
         fs::write(&config_file, "")?;
     }
 
     // Open the config.yaml file in the default editor.
-    // Use the EDITOR environment variable, or default to "vi".
-    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-    Command::new(editor).arg(&config_file).status()?;
+    let editor = editor_command();
+    loop {
+        Command::new(&editor).arg(&config_file).status()?;
+
+        if report_issues()? == 0 {
+            break;
+        }
+        let reopen = Confirm::new("config.yaml has errors. Reopen it in the editor?")
+            .with_default(true)
+            .prompt()?;
+        if !reopen {
+            break;
+        }
+    }
 
     Ok(())
 }
 
 /// Sets the default vault by writing the given vault name to a file in the configuration directory.
-/// The configuration directory is determined by the `NOTEMANCY_CONF_DIR` environment variable.
+/// The configuration directory is determined by `paths::config_dir`.
 pub fn set_default_vault(vault_name: &str) -> Result<(), Box<dyn Error>> {
     // Retrieve the configuration directory.
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")
-        .map_err(|_| "Environment variable NOTEMANCY_CONF_DIR is not set")?;
-    let conf_path = Path::new(&conf_dir);
+    let conf_dir = config_dir()?;
+    let conf_path = conf_dir.as_path();
 
     // Ensure the configuration directory exists.
     if !conf_path.exists() {
-        fs::create_dir_all(&conf_path)?;
+        fs::create_dir_all(conf_path)?;
     }
 
     // Define the file path to store the default vault.
@@ -60,3 +79,594 @@ pub fn set_default_vault(vault_name: &str) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Returns the editor used to open notes: the `editor` key in config.yaml,
+/// then the EDITOR environment variable, defaulting to "vi".
+pub fn editor_command() -> String {
+    read_config()
+        .ok()
+        .and_then(|config| config.get("editor")?.as_str().map(str::to_string))
+        .filter(|editor| !editor.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Settings of the `embedding:` section of config.yaml.
+pub struct EmbeddingConfig {
+    /// Maximum number of words in a chunk of a note.
+    pub chunk_size: usize,
+    /// Number of words shared by consecutive chunks of a section.
+    pub chunk_overlap: usize,
+    /// Number of results returned by semantic search.
+    pub top_k: usize,
+}
+
+impl EmbeddingConfig {
+    /// Reads the settings, using the defaults for anything missing or invalid.
+    pub fn load() -> Self {
+        let config = read_config().ok();
+        let number = |key: &str| {
+            config
+                .as_ref()
+                .and_then(|c| c.get("embedding")?.get(key)?.as_u64())
+                .and_then(|n| usize::try_from(n).ok())
+        };
+        let chunk_size = number("chunk_size").filter(|&n| n > 0).unwrap_or(200);
+        EmbeddingConfig {
+            chunk_size,
+            chunk_overlap: number("chunk_overlap")
+                .filter(|&n| n < chunk_size)
+                .unwrap_or(40.min(chunk_size - 1)),
+            top_k: number("top_k").filter(|&n| n > 0).unwrap_or(10),
+        }
+    }
+}
+
+/// Reads the vault name stored by `set_default_vault`, if any.
+pub fn read_default_vault() -> Result<Option<String>, Box<dyn Error>> {
+    let default_path = config_dir()?.join("default_vault.txt");
+    if !default_path.exists() {
+        return Ok(None);
+    }
+    let vault_name = fs::read_to_string(default_path)?;
+    let trimmed = vault_name.trim();
+    Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+}
+
+/// Removes the default vault file written by `set_default_vault`.
+pub fn clear_default_vault() -> Result<(), Box<dyn Error>> {
+    let default_path = config_dir()?.join("default_vault.txt");
+    if default_path.exists() {
+        fs::remove_file(default_path)?;
+    }
+    Ok(())
+}
+
+/// config.yaml held as raw lines, so that programmatic edits keep the user's
+/// comments, key order and formatting intact.
+///
+/// Only the block-style `vaults:` list and the top-level `default_vault` key
+/// are edited:
+///
+/// ```yaml
+/// default_vault: main
+/// vaults:
+///   - name: main
+///     directory: /home/me/notes
+/// ```
+pub struct ConfigDocument {
+    path: PathBuf,
+    lines: Vec<String>,
+}
+
+/// Location of one `- name: ...` entry of the `vaults:` list.
+struct VaultEntry {
+    /// Index of the line holding `name:`.
+    name_line: usize,
+    /// Line range of the entry, excluding trailing blank and comment lines.
+    start: usize,
+    end: usize,
+    name: String,
+}
+
+/// Location of the `vaults:` block.
+struct VaultsBlock {
+    /// Index of the `vaults:` line.
+    key_line: usize,
+    /// Index just past the last entry line.
+    end: usize,
+    /// Indentation used by the entries' `-` markers.
+    indent: String,
+    /// Line range of every list item, in file order.
+    spans: Vec<(usize, usize)>,
+    /// The items that have a `name:`.
+    entries: Vec<VaultEntry>,
+}
+
+impl ConfigDocument {
+    /// Loads config.yaml from the configuration directory.
+    /// A missing file yields an empty document which is created on `save`.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = config_dir()?.join("config.yaml");
+        let content = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        Ok(ConfigDocument {
+            path,
+            lines: content.lines().map(str::to_string).collect(),
+        })
+    }
+
+    /// Writes the document back to config.yaml.
+    ///
+    /// The edited text is parsed before writing, so a failed edit never
+    /// leaves an unreadable configuration behind.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let mut content = self.lines.join("\n");
+        content.push('\n');
+        serde_yaml::from_str::<serde_yaml::Value>(&content)
+            .map_err(|e| format!("Refusing to write invalid config.yaml: {}", e))?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("yaml.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Appends a vault entry to the `vaults:` list, creating the list if needed.
+    pub fn add_vault(&mut self, name: &str, directory: &Path) -> Result<(), Box<dyn Error>> {
+        let entry = |indent: &str| {
+            vec![
+                format!("{}- name: {}", indent, yaml_scalar(name)),
+                format!(
+                    "{}  directory: {}",
+                    indent,
+                    yaml_scalar(&directory.to_string_lossy())
+                ),
+            ]
+        };
+
+        match self.vaults_block()? {
+            Some(block) => {
+                if block.entries.iter().any(|e| e.name == name) {
+                    return Err(format!("Vault '{}' already exists", name).into());
+                }
+                // An empty flow list (`vaults: []`) is turned into a block list.
+                let (_, comment) =
+                    split_inline_comment(&self.lines[block.key_line]["vaults:".len()..]);
+                self.lines[block.key_line] = format!("vaults: {}", comment).trim_end().to_string();
+                let at = block.end.max(block.key_line + 1);
+                self.lines.splice(at..at, entry(&block.indent));
+            }
+            None => {
+                if self.lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                    self.lines.push(String::new());
+                }
+                self.lines.push("vaults:".to_string());
+                self.lines.extend(entry("  "));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the entry for `name` from the `vaults:` list.
+    pub fn remove_vault(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        let entry = self.find_vault(name)?;
+        self.lines.drain(entry.start..entry.end);
+        Ok(())
+    }
+
+    /// Changes the `name:` of the vault entry `old_name` to `new_name`.
+    pub fn rename_vault(&mut self, old_name: &str, new_name: &str) -> Result<(), Box<dyn Error>> {
+        let block = self
+            .vaults_block()?
+            .ok_or("No vaults defined in config.yaml")?;
+        if block.entries.iter().any(|e| e.name == new_name) {
+            return Err(format!("Vault '{}' already exists", new_name).into());
+        }
+        let entry = self.find_vault(old_name)?;
+        let line = &self.lines[entry.name_line];
+        let value_start = line.find("name:").ok_or("Malformed vault entry")? + "name:".len();
+        let (_, comment) = split_inline_comment(&line[value_start..]);
+        let comment = if comment.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", comment)
+        };
+        self.lines[entry.name_line] = format!(
+            "{} {}{}",
+            &line[..value_start],
+            yaml_scalar(new_name),
+            comment
+        );
+        Ok(())
+    }
+
+    /// Returns the value of the top-level `default_vault` key, if set.
+    pub fn default_vault(&self) -> Option<String> {
+        let line = self.default_vault_line()?;
+        let (value, _) = split_inline_comment(&self.lines[line]["default_vault:".len()..]);
+        let value: serde_yaml::Value = serde_yaml::from_str(value).ok()?;
+        value.as_str().map(str::to_string)
+    }
+
+    /// Sets the top-level `default_vault` key to `name`, keeping its comment,
+    /// or removes the key when `name` is `None`.
+    pub fn set_default_vault(&mut self, name: Option<&str>) {
+        let line = self.default_vault_line();
+        match (line, name) {
+            (Some(line), Some(name)) => {
+                let (_, comment) =
+                    split_inline_comment(&self.lines[line]["default_vault:".len()..]);
+                let comment = if comment.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", comment)
+                };
+                self.lines[line] = format!("default_vault: {}{}", yaml_scalar(name), comment);
+            }
+            (Some(line), None) => {
+                self.lines.remove(line);
+            }
+            (None, Some(name)) => {
+                self.lines
+                    .insert(0, format!("default_vault: {}", yaml_scalar(name)));
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn default_vault_line(&self) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|l| l.starts_with("default_vault:"))
+    }
+
+    fn find_vault(&self, name: &str) -> Result<VaultEntry, Box<dyn Error>> {
+        self.vaults_block()?
+            .and_then(|block| block.entries.into_iter().find(|e| e.name == name))
+            .ok_or_else(|| format!("Vault '{}' not found in configuration", name).into())
+    }
+
+    /// Locates the top-level `vaults:` key and its entries.
+    fn vaults_block(&self) -> Result<Option<VaultsBlock>, Box<dyn Error>> {
+        let Some(key_line) = self.lines.iter().position(|l| l.starts_with("vaults:")) else {
+            return Ok(None);
+        };
+
+        let (inline, _) = split_inline_comment(&self.lines[key_line]["vaults:".len()..]);
+        match inline.trim() {
+            "" => {}
+            "[]" => {
+                return Ok(Some(VaultsBlock {
+                    key_line,
+                    end: key_line + 1,
+                    indent: "  ".to_string(),
+                    spans: Vec::new(),
+                    entries: Vec::new(),
+                }));
+            }
+            _ => {
+                return Err(
+                    "Only block-style 'vaults:' lists can be edited; please edit config.yaml by hand"
+                        .into(),
+                );
+            }
+        }
+
+        // Collect the start line of every entry and the end of the block.
+        let mut indent: Option<String> = None;
+        let mut starts = Vec::new();
+        let mut end = key_line + 1;
+        for (i, line) in self.lines.iter().enumerate().skip(key_line + 1) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let line_indent = &line[..line.len() - trimmed.len()];
+            let is_item = trimmed == "-" || trimmed.starts_with("- ");
+            if line_indent.is_empty() && !is_item {
+                break;
+            }
+            if is_item && indent.as_deref().is_none_or(|ind| ind == line_indent) {
+                indent.get_or_insert_with(|| line_indent.to_string());
+                starts.push(i);
+            }
+            end = i + 1;
+        }
+
+        let mut spans = Vec::new();
+        let mut entries = Vec::new();
+        for (n, &start) in starts.iter().enumerate() {
+            let mut entry_end = starts.get(n + 1).copied().unwrap_or(end);
+            while entry_end > start + 1 {
+                let trimmed = self.lines[entry_end - 1].trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    entry_end -= 1;
+                } else {
+                    break;
+                }
+            }
+            spans.push((start, entry_end));
+            if let Some((name_line, name)) = (start..entry_end).find_map(|i| {
+                let fragment = self.lines[i]
+                    .trim_start()
+                    .trim_start_matches('-')
+                    .trim_start();
+                if !fragment.starts_with("name:") {
+                    return None;
+                }
+                let mapping: serde_yaml::Mapping = serde_yaml::from_str(fragment).ok()?;
+                let name = mapping.get("name")?.as_str()?.to_string();
+                Some((i, name))
+            }) {
+                entries.push(VaultEntry {
+                    name_line,
+                    start,
+                    end: entry_end,
+                    name,
+                });
+            }
+        }
+
+        Ok(Some(VaultsBlock {
+            key_line,
+            end,
+            indent: indent.unwrap_or_else(|| "  ".to_string()),
+            spans,
+            entries,
+        }))
+    }
+
+    /// Returns the path of the underlying config.yaml.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the document text.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Returns the 1-based (line, column) of a top-level key, or of a key nested
+    /// directly below it when `path` has two elements.
+    pub fn key_position(&self, path: &[&str]) -> Option<(usize, usize)> {
+        let (first, rest) = path.split_first()?;
+        let top = self
+            .lines
+            .iter()
+            .position(|l| l.starts_with(&format!("{}:", first)))?;
+        let Some(child) = rest.first() else {
+            return Some((top + 1, 1));
+        };
+        let prefix = format!("{}:", child);
+        for (i, line) in self.lines.iter().enumerate().skip(top + 1) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed.len() == line.len() {
+                break;
+            }
+            if trimmed.starts_with(&prefix) {
+                return Some((i + 1, line.len() - trimmed.len() + 1));
+            }
+        }
+        Some((top + 1, 1))
+    }
+
+    /// Returns the 1-based (line, column) of `field` in the `index`-th `vaults:` item,
+    /// or of the item itself when `field` is `None` or missing.
+    pub fn vault_position(&self, index: usize, field: Option<&str>) -> Option<(usize, usize)> {
+        let block = self.vaults_block().ok()??;
+        let &(start, end) = block.spans.get(index)?;
+        if let Some(field) = field {
+            let prefix = format!("{}:", field);
+            for i in start..end {
+                let line = &self.lines[i];
+                let fragment = line.trim_start().trim_start_matches('-').trim_start();
+                if fragment.starts_with(&prefix) {
+                    return Some((i + 1, line.len() - fragment.len() + 1));
+                }
+            }
+        }
+        let line = &self.lines[start];
+        Some((start + 1, line.len() - line.trim_start().len() + 1))
+    }
+}
+
+/// Splits the value part of a `key: value  # comment` line into the value and the comment.
+fn split_inline_comment(rest: &str) -> (&str, &str) {
+    let trimmed = rest.trim_start();
+    if trimmed.starts_with('#') {
+        return ("", trimmed.trim_end());
+    }
+    let offset = rest.len() - trimmed.len();
+    // Skip over a quoted scalar so that a '#' inside quotes is not taken as a comment.
+    let search_from = match trimmed.chars().next() {
+        Some(q @ ('"' | '\'')) => trimmed[1..].find(q).map_or(trimmed.len(), |p| p + 2),
+        _ => 0,
+    };
+    match trimmed[search_from..].find(" #") {
+        Some(pos) => {
+            let split = offset + search_from + pos;
+            (rest[..split].trim(), rest[split..].trim())
+        }
+        None => (rest.trim(), ""),
+    }
+}
+
+/// Formats `value` as a YAML scalar, quoting it when a plain scalar would be misread.
+pub fn yaml_scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || " _-./~+@".contains(c))
+        && serde_yaml::from_str::<serde_yaml::Value>(value)
+            .is_ok_and(|v| v.as_str() == Some(value));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(text: &str) -> ConfigDocument {
+        ConfigDocument {
+            path: PathBuf::from("config.yaml"),
+            lines: text.lines().map(str::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn default_vault_is_read_without_quotes_or_comment() {
+        assert_eq!(
+            doc("default_vault: main\n").default_vault().as_deref(),
+            Some("main")
+        );
+        assert_eq!(
+            doc("default_vault: \"my notes\"  # home\n")
+                .default_vault()
+                .as_deref(),
+            Some("my notes")
+        );
+        assert_eq!(doc("vaults: []\n").default_vault(), None);
+        assert_eq!(doc("  default_vault: nested\n").default_vault(), None);
+    }
+
+    #[test]
+    fn set_default_vault_replaces_keeps_comment_or_removes() {
+        let mut config = doc("default_vault: main  # primary\nvaults: []");
+        config.set_default_vault(Some("work notes"));
+        assert_eq!(
+            config.text(),
+            "default_vault: work notes # primary\nvaults: []"
+        );
+        assert_eq!(config.default_vault().as_deref(), Some("work notes"));
+        config.set_default_vault(Some("a: b"));
+        assert_eq!(config.default_vault().as_deref(), Some("a: b"));
+        config.set_default_vault(None);
+        assert_eq!(config.text(), "vaults: []");
+        config.set_default_vault(None);
+        assert_eq!(config.text(), "vaults: []");
+    }
+
+    const CONFIG: &str = "\
+# notemancy
+vaults:
+  - name: main  # primary
+    directory: /notes
+
+  # work stuff
+  - name: work
+    directory: /work
+editor: vim
+";
+
+    #[test]
+    fn add_vault_appends_to_the_list() {
+        let mut config = doc(CONFIG);
+        config.add_vault("new", Path::new("/new notes")).unwrap();
+        assert!(config.text().contains(
+            "    directory: /work\n  - name: new\n    directory: /new notes\neditor: vim"
+        ));
+        assert!(config.add_vault("main", Path::new("/x")).is_err());
+    }
+
+    #[test]
+    fn add_vault_creates_or_expands_the_list() {
+        let mut config = doc("editor: vim");
+        config.add_vault("main", Path::new("/notes")).unwrap();
+        assert_eq!(
+            config.text(),
+            "editor: vim\n\nvaults:\n  - name: main\n    directory: /notes"
+        );
+
+        let mut config = doc("vaults: []  # none yet\neditor: vim");
+        config.add_vault("main", Path::new("/notes")).unwrap();
+        assert_eq!(
+            config.text(),
+            "vaults: # none yet\n  - name: main\n    directory: /notes\neditor: vim"
+        );
+    }
+
+    #[test]
+    fn remove_vault_keeps_the_other_entries_and_comments() {
+        let mut config = doc(CONFIG);
+        config.remove_vault("main").unwrap();
+        assert_eq!(
+            config.text(),
+            "# notemancy\nvaults:\n\n  # work stuff\n  - name: work\n    directory: /work\neditor: vim"
+        );
+        assert!(config.remove_vault("main").is_err());
+    }
+
+    #[test]
+    fn rename_vault_keeps_the_inline_comment() {
+        let mut config = doc(CONFIG);
+        config.rename_vault("main", "home: notes").unwrap();
+        assert!(
+            config
+                .text()
+                .contains("  - name: \"home: notes\" # primary\n")
+        );
+        assert!(config.rename_vault("work", "home: notes").is_err());
+        assert!(config.rename_vault("missing", "other").is_err());
+    }
+
+    #[test]
+    fn flow_style_vault_lists_are_not_edited() {
+        let mut config = doc("vaults: [{name: a, directory: /a}]");
+        assert!(config.add_vault("b", Path::new("/b")).is_err());
+        assert!(config.remove_vault("a").is_err());
+    }
+
+    #[test]
+    fn positions_point_at_keys_and_vault_fields() {
+        let config = doc(CONFIG);
+        assert_eq!(config.key_position(&["editor"]), Some((9, 1)));
+        assert_eq!(config.key_position(&["missing"]), None);
+        assert_eq!(config.vault_position(1, Some("directory")), Some((8, 5)));
+        assert_eq!(config.vault_position(0, None), Some((3, 3)));
+        assert_eq!(config.vault_position(2, None), None);
+    }
+
+    #[test]
+    fn inline_comments_are_split_outside_quotes() {
+        assert_eq!(split_inline_comment(" main  # note"), ("main", "# note"));
+        assert_eq!(split_inline_comment(" \"a # b\" # c"), ("\"a # b\"", "# c"));
+        assert_eq!(split_inline_comment(" a#b"), ("a#b", ""));
+        assert_eq!(split_inline_comment("  # only"), ("", "# only"));
+    }
+
+    #[test]
+    fn yaml_scalar_quotes_values_yaml_would_misread() {
+        let cases = [
+            ("main", "main"),
+            ("/home/me/notes", "/home/me/notes"),
+            ("my notes", "my notes"),
+            ("", "\"\""),
+            ("a: b", "\"a: b\""),
+            ("#tag", "\"#tag\""),
+            ("true", "\"true\""),
+            ("2026", "\"2026\""),
+            (" padded", "\" padded\""),
+            ("say \"hi\"", "\"say \\\"hi\\\"\""),
+            ("C:\\notes", "\"C:\\\\notes\""),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(yaml_scalar(value), expected, "{value}");
+            let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml_scalar(value)).unwrap();
+            assert_eq!(parsed.as_str(), Some(value));
+        }
+    }
+}
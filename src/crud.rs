@@ -1,87 +1,374 @@
 // src/crud.rs
 
-use inquire::Text;
-use std::env;
+use chrono::Local;
+use clap::ValueEnum;
+use inquire::{Select, Text};
 use std::error::Error;
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-// Import the create_note method and get_vault_dir from the notemancy-core library.
-use notemancy_core::config::get_vault_dir;
-use notemancy_core::crud::create_note;
+use crate::config::{editor_command, yaml_scalar};
+use crate::frontmatter;
+use crate::history;
+use crate::templates::{self, TemplateVars, render, select_template};
+use crate::vault::VaultContext;
 
-/// A helper function that sanitizes a title string into a valid file name.
-/// This replicates the logic in the core library.
-fn sanitize_title(title: &str) -> String {
+/// File name stem used when a title has no letters or digits at all (e.g. only emoji).
+const UNTITLED: &str = "untitled";
+
+/// Sanitizes a title into a file name stem: the title is lowercased and every
+/// run of characters that are not letters or digits becomes a single '-'.
+///
+/// This follows the core library's private `sanitize_title`, except that titles
+/// without any letters or digits become `untitled`, where the core would name
+/// the file `.md`.
+pub fn sanitize_title(title: &str) -> String {
     let lower = title.trim().to_lowercase();
     let mapped: String = lower
         .chars()
         .map(|c| if c.is_alphanumeric() { c } else { '-' })
         .collect();
-    mapped
+    let sanitized = mapped
         .split('-')
         .filter(|s| !s.is_empty())
         .collect::<Vec<_>>()
-        .join("-")
+        .join("-");
+    if sanitized.is_empty() {
+        UNTITLED.to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// Returns the path a note with `title` in `project` is created at.
+pub fn note_path(vault: &VaultContext, project: &str, title: &str) -> PathBuf {
+    let project_path = if project.is_empty() {
+        vault.dir.clone()
+    } else {
+        vault.dir.join(project)
+    };
+    project_path.join(format!("{}.md", sanitize_title(title)))
 }
 
-/// Creates a new note using interactive prompts.
+/// The frontmatter every new note starts with: the title and date fields the
+/// core library's `create_note` writes, with the title as a YAML scalar so that
+/// titles such as `C++: intro` stay valid YAML.
+pub fn note_skeleton(title: &str) -> String {
+    let today = Local::now().format("%Y-%m-%d");
+    format!("---\ntitle: {}\ndate: {}\n---\n", yaml_scalar(title), today)
+}
+
+/// Writes a new note to `path`, creating its project folders.
 ///
-/// This function prompts the user for the note title, vault (unless a default vault is set),
-/// and an optional project. It then creates the note using the core's create_note function
-/// and finally opens the newly created file in the default editor (determined by the EDITOR environment variable).
-pub fn new_note() -> Result<(), Box<dyn Error>> {
-    // Check if a default vault is set in the config directory.
-    let default_vault = if let Ok(conf_dir) = env::var("NOTEMANCY_CONF_DIR") {
-        let default_path = Path::new(&conf_dir).join("default_vault.txt");
-        if default_path.exists() {
-            let vault_name = fs::read_to_string(default_path)?;
-            let trimmed = vault_name.trim().to_string();
-            if !trimmed.is_empty() {
-                Some(trimmed)
-            } else {
-                None
+/// Notes are created here rather than with the core library's `create_note`,
+/// which derives the path from the title itself, overwrites an existing file
+/// and does not return the path. Here an existing file is never overwritten:
+/// the error then has kind `io::ErrorKind::AlreadyExists`.
+pub fn write_new_note(path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// What to do when the file for a new note already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IfExists {
+    /// Open the existing note instead.
+    Open,
+    /// Create the note with a numeric suffix, e.g. `meeting-2.md`.
+    Suffix,
+    /// Fail without creating anything.
+    Error,
+}
+
+/// Where a new note should go once collisions have been resolved.
+enum Target {
+    /// Create a note with `title` at `path`, which does not exist yet.
+    New { path: PathBuf, title: String },
+    /// Open this existing note instead of creating one.
+    Existing(PathBuf),
+}
+
+/// Returns the first `<stem>-N.md` next to `path` that does not exist, starting at 2.
+fn suffixed_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}.md", stem, n)))
+        .find(|candidate| !candidate.exists())
+        .expect("an unused suffix exists")
+}
+
+/// Describes an existing note for collision prompts, including its title when it
+/// differs from `title` (e.g. "C++" colliding with "C").
+fn describe_existing(path: &Path, title: &str) -> String {
+    let existing_title = fs::read_to_string(path).ok().and_then(|content| {
+        let (fm, _) = frontmatter::split(&content);
+        serde_yaml::from_str::<serde_yaml::Value>(fm?)
+            .ok()?
+            .get("title")?
+            .as_str()
+            .map(str::to_string)
+    });
+    match existing_title {
+        Some(existing) if existing != title => {
+            format!("{} (titled '{}')", path.display(), existing)
+        }
+        _ => path.display().to_string(),
+    }
+}
+
+/// Resolves where a note titled `title` in `project` should be created.
+///
+/// If the file already exists, `if_exists` decides what happens; without it the
+/// user is asked to open the existing note, add a suffix or pick another title
+/// when running interactively, and an error is returned otherwise.
+fn resolve_target(
+    vault: &VaultContext,
+    project: &str,
+    mut title: String,
+    if_exists: Option<IfExists>,
+    interactive: bool,
+) -> Result<Target, Box<dyn Error>> {
+    const OPEN: &str = "Open the existing note";
+    const SUFFIX: &str = "Create it with a numbered file name";
+    const RENAME: &str = "Choose a different title";
+
+    loop {
+        let path = note_path(vault, project, &title);
+        if !path.exists() {
+            return Ok(Target::New { path, title });
+        }
+        let existing = describe_existing(&path, &title);
+
+        let choice = match if_exists {
+            Some(IfExists::Open) => OPEN,
+            Some(IfExists::Suffix) => SUFFIX,
+            Some(IfExists::Error) => {
+                return Err(format!("A note already exists at {}", existing).into());
+            }
+            None if interactive => Select::new(
+                &format!("A note already exists at {}.", existing),
+                vec![OPEN, SUFFIX, RENAME],
+            )
+            .prompt()?,
+            None => {
+                return Err(format!(
+                    "A note already exists at {}; use --if-exists open or --if-exists suffix",
+                    existing
+                )
+                .into());
+            }
+        };
+
+        match choice {
+            OPEN => return Ok(Target::Existing(path)),
+            SUFFIX => {
+                return Ok(Target::New {
+                    path: suffixed_path(&path),
+                    title,
+                });
+            }
+            _ => {
+                title = Text::new("Enter note title:").prompt()?;
+                if title.trim().is_empty() {
+                    return Err("The note title must not be empty".into());
+                }
             }
-        } else {
-            None
         }
-    } else {
-        None
-    };
+    }
+}
 
-    // Prompt for the note title.
-    let title = Text::new("Enter note title:").prompt()?;
+/// Opens `path` in the configured editor (see `editor_command`) and waits for it to exit.
+pub fn open_in_editor(path: &Path) -> Result<(), Box<dyn Error>> {
+    let editor = editor_command();
+    Command::new(editor).arg(path).status()?;
+    Ok(())
+}
 
-    // Use the default vault if set; otherwise, prompt the user for vault name.
-    let vault = if let Some(vault_name) = default_vault {
-        vault_name
-    } else {
-        Text::new("Enter vault name:").prompt()?
+/// Opens `path` in the configured editor with the cursor on `line` (1-based).
+///
+/// Most editors take `+<line>` before the file; VS Code takes `-g <file>:<line>`
+/// and Helix and Sublime Text take `<file>:<line>`.
+pub fn open_in_editor_at(path: &Path, line: usize) -> Result<(), Box<dyn Error>> {
+    let editor = editor_command();
+    let name = Path::new(&editor)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut command = Command::new(&editor);
+    match name.as_str() {
+        "code" | "codium" => command
+            .arg("-g")
+            .arg(format!("{}:{}", path.display(), line)),
+        "hx" | "helix" | "subl" => command.arg(format!("{}:{}", path.display(), line)),
+        _ => command.arg(format!("+{}", line)).arg(path),
     };
+    command.status()?;
+    Ok(())
+}
 
-    // Prompt for an optional project; if nothing is provided, default to an empty string.
-    let project = Text::new("Enter project (optional):")
-        .with_default("")
-        .prompt()?;
+/// Options for `new_note`; anything left unset is prompted for when running interactively.
+#[derive(Default)]
+pub struct NewNoteOptions {
+    pub title: Option<String>,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    /// Template to render the note from; an empty name shows the template picker.
+    pub template: Option<String>,
+    /// What to do when a note with the same file name exists; `None` asks.
+    pub if_exists: Option<IfExists>,
+    /// Skip opening the created note in the editor.
+    pub no_edit: bool,
+}
 
-    // Create the note using the core library's create_note function.
-    create_note(&vault, &project, &title)?;
+/// Creates a new note, prompting for anything not given in `options`.
+///
+/// When a title is passed on the command line no prompts are shown. If stdin is
+/// not a terminal, it is read as the body of the note and the editor is not opened,
+/// so notes can be filed from scripts (`echo "..." | ncy n -t "Quick"`).
+/// With a template (see `crate::templates`) the rendered template replaces the
+/// default frontmatter if it has its own, and is appended to it otherwise.
+/// Tags are written to the frontmatter. Existing notes are never overwritten;
+/// see `IfExists` for how a clashing file name is handled. The path of the
+/// created (or opened) note is printed,
+/// and unless `no_edit` is set the note is then opened in the configured editor
+/// (see `editor_command`).
+pub fn new_note(vault: &VaultContext, options: NewNoteOptions) -> Result<(), Box<dyn Error>> {
+    let interactive = io::stdin().is_terminal();
 
-    // Reconstruct the file path for the newly created note.
-    let vault_dir = get_vault_dir(&vault)?;
-    let project_path = if project.is_empty() {
-        PathBuf::from(vault_dir)
+    let (title, project) = match options.title {
+        Some(title) => (title, options.project.unwrap_or_default()),
+        None if interactive => {
+            // Prompt for the note title.
+            let title = Text::new("Enter note title:").prompt()?;
+            // Prompt for an optional project; if nothing is provided, default to an empty string.
+            let project = match options.project {
+                Some(project) => project,
+                None => Text::new("Enter project (optional):")
+                    .with_default("")
+                    .prompt()?,
+            };
+            (title, project)
+        }
+        None => {
+            return Err("A title is required when not running interactively; use --title".into());
+        }
+    };
+    if title.trim().is_empty() {
+        return Err("The note title must not be empty".into());
+    }
+
+    // Read the body from stdin when it is piped in.
+    let body = if interactive {
+        String::new()
     } else {
-        Path::new(&vault_dir).join(&project)
+        let mut body = String::new();
+        io::stdin().read_to_string(&mut body)?;
+        body
     };
-    let sanitized_title = sanitize_title(&title);
-    let file_name = format!("{}.md", sanitized_title);
-    let file_path = project_path.join(file_name);
+
+    let (file_path, title) =
+        match resolve_target(vault, &project, title, options.if_exists, interactive)? {
+            Target::New { path, title } => (path, title),
+            Target::Existing(path) => {
+                println!("{}", path.display());
+                if interactive && !options.no_edit {
+                    open_in_editor(&path)?;
+                    history::record_open(vault, &path);
+                }
+                return Ok(());
+            }
+        };
+
+    // Render the template before creating anything, so a failed prompt leaves no file behind.
+    let rendered = match options.template.as_deref() {
+        Some(name) => {
+            let template = select_template(vault, name)?;
+            let vars = TemplateVars {
+                title: &title,
+                project: &project,
+                vault: &vault.name,
+            };
+            Some(render(
+                &fs::read_to_string(&template.path)?,
+                &vars,
+                interactive,
+            )?)
+        }
+        None => None,
+    };
+
+    let mut content = note_skeleton(&title);
+    if let Some(rendered) = rendered {
+        content = templates::apply(content, &rendered);
+    }
+    content = frontmatter::add_tags(&content, &options.tags);
+    content.push_str(&body);
+    write_new_note(&file_path, &content)?;
+
+    println!("{}", file_path.display());
 
     // Open the newly created note in the default editor.
-    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-    Command::new(editor).arg(file_path).status()?;
+    if interactive && !options.no_edit {
+        open_in_editor(&file_path)?;
+        history::record_open(vault, &file_path);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_lowercases_and_joins_words() {
+        assert_eq!(sanitize_title("Meeting Notes"), "meeting-notes");
+        assert_eq!(sanitize_title("  Weekly   sync -- Q3  "), "weekly-sync-q3");
+        assert_eq!(sanitize_title("a__b..c"), "a-b-c");
+    }
+
+    #[test]
+    fn sanitize_collapses_symbols() {
+        assert_eq!(sanitize_title("C++"), "c");
+        assert_eq!(sanitize_title("C"), "c");
+        assert_eq!(sanitize_title("C#/.NET"), "c-net");
+        assert_eq!(sanitize_title("what? why!"), "what-why");
+    }
+
+    #[test]
+    fn sanitize_keeps_unicode_letters() {
+        assert_eq!(sanitize_title("Café Déjà Vu"), "café-déjà-vu");
+        assert_eq!(sanitize_title("ÉCOLE"), "école");
+        assert_eq!(sanitize_title("日本語のメモ"), "日本語のメモ");
+        assert_eq!(sanitize_title("Straße"), "straße");
+    }
+
+    #[test]
+    fn sanitize_drops_emoji() {
+        assert_eq!(sanitize_title("🎉 Party plan"), "party-plan");
+        assert_eq!(sanitize_title("ideas 💡💡"), "ideas");
+    }
+
+    #[test]
+    fn sanitize_falls_back_for_empty_titles() {
+        assert_eq!(sanitize_title(""), UNTITLED);
+        assert_eq!(sanitize_title("   "), UNTITLED);
+        assert_eq!(sanitize_title("🎉🎉"), UNTITLED);
+        assert_eq!(sanitize_title("?!"), UNTITLED);
+    }
+
+    #[test]
+    fn skeleton_quotes_titles_that_are_not_plain_yaml() {
+        assert!(note_skeleton("Plain title").starts_with("---\ntitle: Plain title\n"));
+        assert!(note_skeleton("C++: intro").starts_with("---\ntitle: \"C++: intro\"\n"));
+    }
+}
@@ -0,0 +1,200 @@
+// src/daily.rs
+
+use chrono::{Days, Local, NaiveDate};
+use notemancy_core::config::read_config;
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+
+use crate::crud::{note_path, note_skeleton, open_in_editor, write_new_note};
+use crate::templates::{self, TemplateVars, render, select_template, try_format};
+use crate::vault::VaultContext;
+
+/// How far back `rollover` looks for the previous periodic note, in periods.
+const ROLLOVER_LOOKBACK: u64 = 31;
+
+/// The period a note covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+impl Period {
+    fn days(self) -> u64 {
+        match self {
+            Period::Day => 1,
+            Period::Week => 7,
+        }
+    }
+}
+
+/// Settings from the `daily:` section of config.yaml.
+struct DailyConfig {
+    /// Project folder inside the vault that periodic notes are kept in.
+    folder: String,
+    /// chrono format of daily note titles; the file name is the sanitized title.
+    format: String,
+    /// chrono format of weekly note titles.
+    week_format: String,
+    template: Option<String>,
+    week_template: Option<String>,
+    rollover: bool,
+}
+
+impl DailyConfig {
+    fn load() -> Self {
+        let config = read_config().ok();
+        let daily = config.as_ref().and_then(|c| c.get("daily"));
+        let string = |key: &str| {
+            daily
+                .and_then(|d| d.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        DailyConfig {
+            folder: string("folder").unwrap_or_else(|| "daily".to_string()),
+            format: string("format").unwrap_or_else(|| "%Y-%m-%d".to_string()),
+            week_format: string("week_format").unwrap_or_else(|| "%G-W%V".to_string()),
+            template: string("template"),
+            week_template: string("week_template"),
+            rollover: daily
+                .and_then(|d| d.get("rollover"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }
+    }
+
+    fn title(&self, period: Period, date: NaiveDate) -> Result<String, Box<dyn Error>> {
+        let format = match period {
+            Period::Day => &self.format,
+            Period::Week => &self.week_format,
+        };
+        Ok(try_format(date.format(format))
+            .map_err(|_| format!("Invalid date format '{}' in config.yaml", format))?)
+    }
+}
+
+/// Options for `open_periodic`.
+pub struct PeriodicOptions {
+    /// Overrides the `daily.rollover` setting when given.
+    pub rollover: Option<bool>,
+    /// Print the path of the note without opening the editor.
+    pub no_edit: bool,
+}
+
+/// Returns today's date in the local time zone.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Implements `ncy today`, `ncy yesterday`, `ncy week` and `ncy journal`:
+/// creates the note for the `period` containing `date` unless it exists, prints
+/// its path and opens it in the editor.
+///
+/// Notes live in the `daily.folder` project of the vault and are titled with the
+/// `daily.format` (or `daily.week_format`) chrono pattern. A new note is rendered
+/// from `daily.template` (or `daily.week_template`) when set. With rollover
+/// enabled, unchecked `- [ ]` tasks from the most recent earlier note are copied
+/// into a newly created one.
+pub fn open_periodic(
+    vault: &VaultContext,
+    period: Period,
+    date: NaiveDate,
+    options: PeriodicOptions,
+) -> Result<(), Box<dyn Error>> {
+    let config = DailyConfig::load();
+    let interactive = io::stdin().is_terminal();
+    let title = config.title(period, date)?;
+    let path = note_path(vault, &config.folder, &title);
+
+    if !path.exists() {
+        let template = match period {
+            Period::Day => &config.template,
+            Period::Week => &config.week_template,
+        };
+        let rendered = match template {
+            Some(name) => {
+                let template = select_template(vault, name)?;
+                let vars = TemplateVars {
+                    title: &title,
+                    project: &config.folder,
+                    vault: &vault.name,
+                };
+                Some(render(
+                    &fs::read_to_string(&template.path)?,
+                    &vars,
+                    interactive,
+                )?)
+            }
+            None => None,
+        };
+
+        let mut content = note_skeleton(&title);
+        if let Some(rendered) = rendered {
+            content = templates::apply(content, &rendered);
+        }
+        if options.rollover.unwrap_or(config.rollover)
+            && let Some(rollover) = unfinished_tasks(vault, &config, period, date)?
+        {
+            if !content.is_empty() && !content.ends_with("\n\n") {
+                content.push('\n');
+            }
+            for task in &rollover.tasks {
+                content.push_str(task);
+                content.push('\n');
+            }
+            status!(
+                "Rolled over {} unfinished task(s) from {}",
+                rollover.tasks.len(),
+                rollover.from.display()
+            );
+        }
+        write_new_note(&path, &content)?;
+    }
+
+    println!("{}", path.display());
+
+    if interactive && !options.no_edit {
+        open_in_editor(&path)?;
+    }
+    Ok(())
+}
+
+/// Unfinished tasks carried over from an earlier note.
+struct Rollover {
+    from: PathBuf,
+    tasks: Vec<String>,
+}
+
+/// Finds the most recent note before the one for `date` and returns its path
+/// with its unchecked tasks, if it has any.
+fn unfinished_tasks(
+    vault: &VaultContext,
+    config: &DailyConfig,
+    period: Period,
+    date: NaiveDate,
+) -> Result<Option<Rollover>, Box<dyn Error>> {
+    for i in 1..=ROLLOVER_LOOKBACK {
+        let Some(earlier) = date.checked_sub_days(Days::new(i * period.days())) else {
+            break;
+        };
+        let path = note_path(vault, &config.folder, &config.title(period, earlier)?);
+        if !path.exists() {
+            continue;
+        }
+        let tasks: Vec<String> = fs::read_to_string(&path)?
+            .lines()
+            .filter(|line| {
+                let item = line.trim_start();
+                ["- [ ]", "* [ ]", "+ [ ]"]
+                    .iter()
+                    .any(|marker| item.starts_with(marker))
+            })
+            .map(str::to_string)
+            .collect();
+        return Ok((!tasks.is_empty()).then_some(Rollover { from: path, tasks }));
+    }
+    Ok(None)
+}
@@ -0,0 +1,528 @@
+// src/finder.rs
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use nucleo_picker::nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_picker::nucleo::{Config, Matcher, Nucleo, Utf32Str};
+use std::cmp::Reverse;
+use std::error::Error;
+use std::io::{self, Stderr, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
+
+/// How often the screen is refreshed while a preview is still loading.
+const TICK: Duration = Duration::from_millis(100);
+
+/// How often the screen is refreshed while matching is still running.
+const FRAME: Duration = Duration::from_millis(16);
+
+/// How long each frame waits for the matcher threads, in milliseconds.
+const MATCH_TIMEOUT: u64 = 10;
+
+/// Narrowest terminal that has room for the preview pane.
+const MIN_PREVIEW_WIDTH: u16 = 60;
+
+/// Supplies the contents of the finder's preview pane.
+pub trait Preview {
+    /// Returns the lines to show for `items[index]`. Called only for the
+    /// highlighted item, whenever the screen is drawn.
+    fn lines(&mut self, index: usize) -> Vec<String>;
+
+    /// Returns true while background work may still change the preview, so the
+    /// finder keeps redrawing without waiting for a key press.
+    fn pending(&self) -> bool {
+        false
+    }
+}
+
+/// Narrows the items before fuzzy matching, using filter terms typed into the query.
+pub trait Filter {
+    /// Returns true if `word`, a whitespace-separated word of the query, is a
+    /// filter term rather than text to match.
+    fn is_term(&self, word: &str) -> bool;
+
+    /// Returns true if `items[index]` satisfies every one of `terms`.
+    fn keep(&mut self, index: usize, terms: &[&str]) -> bool;
+}
+
+/// Options for `find`.
+#[derive(Default)]
+pub struct FinderOptions<'a> {
+    /// Shows a preview of the highlighted item next to the list; toggled with ctrl-t.
+    pub preview: Option<&'a mut dyn Preview>,
+    /// Recognizes filter terms in the query and narrows the items by them.
+    pub filter: Option<&'a mut dyn Filter>,
+    /// Tunes the matcher for file paths.
+    pub match_paths: bool,
+    /// Initial contents of the query line.
+    pub query: String,
+}
+
+/// Runs a full-screen fuzzy finder over `items` and returns the index of the
+/// chosen item, or `None` if the finder was closed with esc or ctrl-c.
+///
+/// The finder draws on stderr so that stdout stays free for results. It draws
+/// its own screen because nucleo-picker's `Picker` has neither a preview pane
+/// nor marks, but matches with the same engine: items are scored on nucleo's
+/// worker threads, and the screen shows the latest results while they run.
+pub fn find<'a>(
+    items: &'a [String],
+    options: FinderOptions<'a>,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    Ok(run(items, options, false)?.and_then(|chosen| chosen.first().copied()))
+}
+
+/// Like `find`, but tab and shift-tab mark items, and enter returns the indices
+/// of all marked items in the order they were marked. Without marks, enter
+/// returns just the highlighted item.
+pub fn find_many<'a>(
+    items: &'a [String],
+    options: FinderOptions<'a>,
+) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+    run(items, options, true)
+}
+
+fn run<'a>(
+    items: &'a [String],
+    options: FinderOptions<'a>,
+    multi: bool,
+) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+    let config = if options.match_paths {
+        Config::DEFAULT.match_paths()
+    } else {
+        Config::DEFAULT
+    };
+    let mut finder = Finder {
+        items,
+        show_preview: options.preview.is_some(),
+        preview: options.preview,
+        filter: options.filter,
+        multi,
+        engine: Nucleo::new(config.clone(), Arc::new(|| {}), None, 1),
+        matcher: Matcher::new(config),
+        query: options.query,
+        terms: Vec::new(),
+        text: String::new(),
+        marked: Vec::new(),
+        selected: 0,
+        offset: 0,
+    };
+    finder.inject(&[]);
+    finder.update_matches();
+
+    let _guard = TerminalGuard::enter()?;
+    let mut out = io::stderr();
+    let mut redraw = true;
+    loop {
+        let status = finder.engine.tick(MATCH_TIMEOUT);
+        if status.changed {
+            finder.move_selection(0);
+            redraw = true;
+        }
+        if redraw {
+            finder.draw(&mut out)?;
+        }
+        let timeout = if status.running { FRAME } else { TICK };
+        if !event::poll(timeout)? {
+            redraw = finder.show_preview && finder.preview.as_ref().is_some_and(|p| p.pending());
+            continue;
+        }
+        redraw = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match finder.handle_key(key) {
+                Action::Continue => true,
+                Action::Accept => {
+                    if !finder.marked.is_empty() {
+                        return Ok(Some(finder.marked));
+                    }
+                    return Ok(finder.highlighted().map(|index| vec![index]));
+                }
+                Action::Abort => return Ok(None),
+            },
+            Event::Resize(..) => true,
+            _ => false,
+        };
+    }
+}
+
+enum Action {
+    Continue,
+    Accept,
+    Abort,
+}
+
+struct Finder<'a> {
+    items: &'a [String],
+    preview: Option<&'a mut dyn Preview>,
+    filter: Option<&'a mut dyn Filter>,
+    show_preview: bool,
+    multi: bool,
+    /// Matches the items on worker threads; each holds its index into `items`.
+    engine: Nucleo<usize>,
+    /// Finds the matched characters of the visible items, to highlight them.
+    matcher: Matcher,
+    query: String,
+    /// The filter terms the items in `engine` were narrowed by.
+    terms: Vec<String>,
+    /// The text `engine` matches against.
+    text: String,
+    /// Indices into `items` of the marked items, in the order they were marked.
+    marked: Vec<usize>,
+    /// Position of the highlighted item among the matches.
+    selected: usize,
+    /// Position among the matches of the first visible item.
+    offset: usize,
+}
+
+impl Finder<'_> {
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Action::Abort,
+            KeyCode::Char('c' | 'g' | 'q') if ctrl => return Action::Abort,
+            KeyCode::Enter => return Action::Accept,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-(self.list_height() as isize)),
+            KeyCode::PageDown => self.move_selection(self.list_height() as isize),
+            KeyCode::Char('t') if ctrl => self.show_preview = !self.show_preview,
+            KeyCode::Tab if self.multi => {
+                self.toggle_mark();
+                self.move_selection(1);
+            }
+            KeyCode::BackTab if self.multi => {
+                self.toggle_mark();
+                self.move_selection(-1);
+            }
+            KeyCode::Char('u') if ctrl => self.set_query(String::new()),
+            KeyCode::Char('w') if ctrl => {
+                let trimmed = self.query.trim_end();
+                let cut = trimmed.rfind(' ').map_or(0, |i| i + 1);
+                self.set_query(trimmed[..cut].to_string());
+            }
+            KeyCode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                let query = format!("{}{}", self.query, c);
+                self.set_query(query);
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn set_query(&mut self, query: String) {
+        if query != self.query {
+            self.query = query;
+            self.update_matches();
+        }
+    }
+
+    /// Hands the new query to the matcher. The items are narrowed again only
+    /// when the filter terms change, not on every key typed into the text.
+    fn update_matches(&mut self) {
+        let (terms, text) = self.split_query();
+        if terms != self.terms {
+            self.inject(&terms);
+        }
+        if text != self.text {
+            let append = text.starts_with(&self.text);
+            self.engine.pattern.reparse(
+                0,
+                &text,
+                CaseMatching::Smart,
+                Normalization::Smart,
+                append,
+            );
+            self.text = text;
+        }
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    /// Replaces the items in the matcher with those satisfying `terms`.
+    fn inject(&mut self, terms: &[String]) {
+        self.engine.restart(true);
+        let injector = self.engine.injector();
+        let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+        for (index, item) in self.items.iter().enumerate() {
+            let keep = match self.filter.as_mut() {
+                Some(filter) if !terms.is_empty() => filter.keep(index, &terms),
+                _ => true,
+            };
+            if keep {
+                injector.push(index, |_, columns| columns[0] = item.as_str().into());
+            }
+        }
+        self.terms = terms.iter().map(|term| term.to_string()).collect();
+    }
+
+    fn match_count(&self) -> usize {
+        self.engine.snapshot().matched_item_count() as usize
+    }
+
+    /// Returns the index into `items` of the highlighted item.
+    fn highlighted(&self) -> Option<usize> {
+        let item = self
+            .engine
+            .snapshot()
+            .get_matched_item(self.selected as u32)?;
+        Some(*item.data)
+    }
+
+    /// Separates the filter terms of the query from the text to match.
+    fn split_query(&self) -> (Vec<String>, String) {
+        let Some(filter) = self.filter.as_ref() else {
+            return (Vec::new(), self.query.clone());
+        };
+        let (terms, words): (Vec<&str>, Vec<&str>) = self
+            .query
+            .split_whitespace()
+            .partition(|word| filter.is_term(word));
+        let terms = terms.into_iter().map(str::to_string).collect();
+        (terms, words.join(" "))
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(index) = self.highlighted() else {
+            return;
+        };
+        match self.marked.iter().position(|&m| m == index) {
+            Some(pos) => {
+                self.marked.remove(pos);
+            }
+            None => self.marked.push(index),
+        }
+    }
+
+    /// Moves the highlight by `delta` items, keeping it on a match.
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.match_count().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn list_height(&self) -> usize {
+        let (_, rows) = terminal::size().unwrap_or((80, 24));
+        rows.saturating_sub(1).max(1) as usize
+    }
+
+    fn draw(&mut self, out: &mut Stderr) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let height = rows.saturating_sub(1) as usize;
+        let preview_shown =
+            self.show_preview && self.preview.is_some() && cols >= MIN_PREVIEW_WIDTH;
+        let list_width = if preview_shown { cols / 2 } else { cols } as usize;
+
+        // Keep the highlighted item on screen.
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if height > 0 && self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+
+        let matched = self.match_count();
+        let count = if self.marked.is_empty() {
+            format!(" {}/{}", matched, self.items.len())
+        } else {
+            format!(
+                " ({} marked) {}/{}",
+                self.marked.len(),
+                matched,
+                self.items.len()
+            )
+        };
+        let prompt = truncate(
+            &format!("> {}", self.query),
+            (cols as usize).saturating_sub(count.len()),
+        );
+        queue!(
+            out,
+            Print(&prompt),
+            cursor::MoveTo(cols.saturating_sub(count.len() as u16), 0),
+            SetAttribute(Attribute::Dim),
+            Print(&count),
+            SetAttribute(Attribute::Reset),
+        )?;
+
+        let snapshot = self.engine.snapshot();
+        let pattern = snapshot.pattern().column_pattern(0);
+        let end = matched.min(self.offset + height);
+        let mut indices = Vec::new();
+        for (row, entry) in snapshot
+            .matched_items(self.offset as u32..end as u32)
+            .enumerate()
+        {
+            let index = *entry.data;
+            let item = &self.items[index];
+            let is_selected = self.offset + row == self.selected;
+            indices.clear();
+            pattern.indices(
+                entry.matcher_columns[0].slice(..),
+                &mut self.matcher,
+                &mut indices,
+            );
+
+            queue!(out, cursor::MoveTo(0, row as u16 + 1))?;
+            if is_selected {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            let gutter = match (is_selected, self.marked.contains(&index)) {
+                (true, true) => ">+",
+                (true, false) => "> ",
+                (false, true) => " +",
+                (false, false) => "  ",
+            };
+            queue!(out, Print(gutter))?;
+            let mut width = 2;
+            for (i, c) in display_chars(item) {
+                let w = c.width().unwrap_or(0);
+                if width + w > list_width {
+                    break;
+                }
+                width += w;
+                if indices.contains(&(i as u32)) {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Bold),
+                        Print(c),
+                        SetAttribute(Attribute::NormalIntensity)
+                    )?;
+                } else {
+                    queue!(out, Print(c))?;
+                }
+            }
+            if is_selected {
+                queue!(out, Print(" ".repeat(list_width.saturating_sub(width))))?;
+            }
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        if preview_shown {
+            let lines = match (self.highlighted(), self.preview.as_mut()) {
+                (Some(index), Some(preview)) => preview.lines(index),
+                _ => Vec::new(),
+            };
+            let preview_width = (cols as usize).saturating_sub(list_width + 2);
+            for row in 0..height {
+                queue!(
+                    out,
+                    cursor::MoveTo(list_width as u16, row as u16 + 1),
+                    Print("│ ")
+                )?;
+                if let Some(line) = lines.get(row) {
+                    queue!(out, Print(truncate(line, preview_width)))?;
+                }
+            }
+        }
+
+        let prompt_width: usize = prompt.chars().map(|c| c.width().unwrap_or(0)).sum();
+        queue!(out, cursor::MoveTo(prompt_width as u16, 0))?;
+        out.flush()
+    }
+}
+
+/// Returns the indices of the `items` matching `query`, best first, as the
+/// finder would list them. Equally good matches keep their original order; an
+/// empty query matches everything. Used to print results when no terminal is
+/// attached.
+pub fn filter(items: &[String], query: &str) -> Vec<usize> {
+    let mut matcher = Matcher::new(Config::DEFAULT);
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut buf = Vec::new();
+    let mut matches: Vec<(usize, u32)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let score = pattern.score(Utf32Str::new(item, &mut buf), &mut matcher)?;
+            Some((index, score))
+        })
+        .collect();
+    matches.sort_by_key(|&(_, score)| Reverse(score));
+    matches.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Cuts `text` to at most `width` terminal columns, drawn as by `display_chars`.
+fn truncate(text: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for (_, c) in display_chars(text) {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out
+}
+
+/// The characters of `text` as they are drawn, each with the index of the
+/// character it comes from: tabs become four spaces, and other control
+/// characters, which would move the cursor, become '?'.
+fn display_chars(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    text.chars().enumerate().flat_map(|(i, c)| match c {
+        '\t' => std::iter::repeat_n((i, ' '), 4),
+        c if c.is_control() => std::iter::repeat_n((i, '?'), 1),
+        c => std::iter::repeat_n((i, c), 1),
+    })
+}
+
+/// Puts the terminal in raw mode on the alternate screen for as long as it lives.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(err) = execute!(io::stderr(), terminal::EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(err);
+        }
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_chars_expand_tabs_and_replace_control_characters() {
+        let chars: Vec<(usize, char)> = display_chars("a\tb\rc").collect();
+        assert_eq!(
+            chars,
+            [
+                (0, 'a'),
+                (1, ' '),
+                (1, ' '),
+                (1, ' '),
+                (1, ' '),
+                (2, 'b'),
+                (3, '?'),
+                (4, 'c')
+            ]
+        );
+    }
+
+    #[test]
+    fn truncate_counts_tabs_and_wide_characters() {
+        assert_eq!(truncate("a\tb", 4), "a   ");
+        assert_eq!(truncate("日本語", 5), "日本");
+        assert_eq!(truncate("x\u{1b}[2J", 3), "x?[");
+    }
+}
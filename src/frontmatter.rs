@@ -0,0 +1,169 @@
+// src/frontmatter.rs
+
+use crate::config::yaml_scalar;
+
+/// Splits a note into its YAML frontmatter (without the `---` delimiters) and body.
+///
+/// The delimiters are the ones written by the core library's `create_note`:
+/// the note starts with `---` and the frontmatter ends at the next `\n---\n`
+/// (or a trailing `\n---`).
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return (None, content);
+    };
+    if let Some(pos) = rest.find("\n---\n") {
+        return (Some(&rest[..pos]), &rest[pos + 5..]);
+    }
+    if let Some(fm) = rest.strip_suffix("\n---") {
+        return (Some(fm), "");
+    }
+    (None, content)
+}
+
+/// Sets the top-level frontmatter `key` to `value`, which must already be valid YAML.
+///
+/// An existing entry (including any indented continuation lines) is replaced in
+/// place; otherwise the key is appended to the frontmatter. A note without
+/// frontmatter gets one.
+pub fn set_field(content: &str, key: &str, value: &str) -> String {
+    let entry = format!("{}: {}", key, value);
+    let (fm, body) = split(content);
+    let Some(fm) = fm else {
+        return format!("---\n{}\n---\n{}", entry, content);
+    };
+
+    let prefix = format!("{}:", key);
+    let mut lines: Vec<&str> = fm.lines().collect();
+    match lines.iter().position(|l| l.starts_with(&prefix)) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|l| !(l.starts_with(' ') || l.starts_with('\t') || l.is_empty()))
+                .map_or(lines.len(), |p| start + 1 + p);
+            lines.splice(start..end, [entry.as_str()]);
+        }
+        None => lines.push(&entry),
+    }
+    format!("---\n{}\n---\n{}", lines.join("\n"), body)
+}
+
+/// Returns the tags listed in the frontmatter `tags` field, which may be a list
+/// or a single string.
+pub fn tags(content: &str) -> Vec<String> {
+    let Some(fm) = split(content).0 else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(fm) else {
+        return Vec::new();
+    };
+    match value.get("tags") {
+        Some(serde_yaml::Value::Sequence(tags)) => tags
+            .iter()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect(),
+        Some(serde_yaml::Value::String(tag)) => vec![tag.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Adds `new_tags` (with or without a leading '#') to the frontmatter `tags`
+/// list, skipping tags the note already has.
+pub fn add_tags(content: &str, new_tags: &[String]) -> String {
+    let mut all = tags(content);
+    let before = all.len();
+    for tag in new_tags {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty() && !all.iter().any(|t| t == tag) {
+            all.push(tag.to_string());
+        }
+    }
+    if all.len() == before {
+        return content.to_string();
+    }
+    let list: Vec<String> = all.iter().map(|tag| yaml_scalar(tag)).collect();
+    set_field(content, "tags", &format!("[{}]", list.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags_of(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn split_separates_frontmatter_and_body() {
+        assert_eq!(split("---\na: 1\n---\nbody\n"), (Some("a: 1"), "body\n"));
+        assert_eq!(split("---\na: 1\n---"), (Some("a: 1"), ""));
+        assert_eq!(split("body\n---\n"), (None, "body\n---\n"));
+        assert_eq!(split("---\nunclosed\n"), (None, "---\nunclosed\n"));
+    }
+
+    #[test]
+    fn set_field_replaces_an_entry_in_place() {
+        let content = "---\ntitle: Old\ntags:\n  - a\n  - b\ndate: 2026-10-17\n---\nbody\n";
+        assert_eq!(
+            set_field(content, "tags", "[c]"),
+            "---\ntitle: Old\ntags: [c]\ndate: 2026-10-17\n---\nbody\n"
+        );
+        assert_eq!(
+            set_field(content, "title", "New"),
+            "---\ntitle: New\ntags:\n  - a\n  - b\ndate: 2026-10-17\n---\nbody\n"
+        );
+    }
+
+    #[test]
+    fn set_field_appends_missing_keys_and_creates_frontmatter() {
+        assert_eq!(
+            set_field("---\ntitle: T\n---\nbody", "related", "[x]"),
+            "---\ntitle: T\nrelated: [x]\n---\nbody"
+        );
+        assert_eq!(
+            set_field("body\n", "title", "T"),
+            "---\ntitle: T\n---\nbody\n"
+        );
+    }
+
+    #[test]
+    fn set_field_matches_whole_top_level_keys_only() {
+        let content = "---\ntitles: keep\nmeta:\n  title: nested\n---\n";
+        assert_eq!(
+            set_field(content, "title", "T"),
+            "---\ntitles: keep\nmeta:\n  title: nested\ntitle: T\n---\n"
+        );
+    }
+
+    #[test]
+    fn tags_reads_lists_and_single_strings() {
+        assert_eq!(tags("---\ntags: [a, b]\n---\n"), ["a", "b"]);
+        assert_eq!(tags("---\ntags:\n  - a\n  - 1\n---\n"), ["a"]);
+        assert_eq!(tags("---\ntags: solo\n---\n"), ["solo"]);
+        assert!(tags("---\ntitle: T\n---\n").is_empty());
+        assert!(tags("no frontmatter").is_empty());
+    }
+
+    #[test]
+    fn add_tags_merges_without_duplicates() {
+        let content = "---\ntitle: T\ntags: [a]\n---\nbody";
+        assert_eq!(
+            add_tags(content, &tags_of(&["#b", "a", " c ", "#", "b"])),
+            "---\ntitle: T\ntags: [a, b, c]\n---\nbody"
+        );
+        assert_eq!(add_tags(content, &tags_of(&["#a"])), content);
+    }
+
+    #[test]
+    fn add_tags_quotes_tags_and_creates_the_field() {
+        assert_eq!(
+            add_tags("---\ntitle: T\n---\n", &tags_of(&["a: b", "2026"])),
+            "---\ntitle: T\ntags: [\"a: b\", \"2026\"]\n---\n"
+        );
+        assert_eq!(
+            add_tags("body", &tags_of(&["x"])),
+            "---\ntags: [x]\n---\nbody"
+        );
+        let tagged = add_tags("---\ntags: solo\n---\n", &tags_of(&["more"]));
+        assert_eq!(tags(&tagged), ["solo", "more"]);
+    }
+}
@@ -0,0 +1,83 @@
+// src/grep.rs
+
+use notemancy_core::crud::read_note;
+use std::error::Error;
+use std::io::{self, IsTerminal};
+
+use crate::crud::open_in_editor_at;
+use crate::finder::{FinderOptions, filter, find};
+use crate::frontmatter;
+use crate::notes::list_notes;
+use crate::preview::LinePreview;
+use crate::vault::VaultContext;
+
+/// Longest part of a line that is indexed; the rest is cut off.
+const MAX_LINE_CHARS: usize = 300;
+
+/// A non-empty line of a note body.
+pub struct ContentLine {
+    pub relpath: String,
+    /// 1-based line number in the file, counting the frontmatter.
+    pub line: usize,
+    pub text: String,
+}
+
+/// Collects every non-empty body line of the notes in `vault`.
+fn index(vault: &VaultContext) -> Result<Vec<ContentLine>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for note in list_notes(&vault.name)? {
+        let Ok(content) = read_note(&vault.name, &note.relpath, true) else {
+            continue;
+        };
+        let (_, body) = frontmatter::split(&content);
+        let offset = content[..content.len() - body.len()].lines().count();
+        for (i, text) in body.lines().enumerate() {
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            lines.push(ContentLine {
+                relpath: note.relpath.clone(),
+                line: offset + i + 1,
+                text: text.chars().take(MAX_LINE_CHARS).collect(),
+            });
+        }
+    }
+    Ok(lines)
+}
+
+/// Implements `ncy grep`: fuzzy-searches the body lines of every note in `vault`
+/// and opens the chosen note in the editor at the matching line.
+///
+/// Lines are matched as `relpath:line: text`, so the query can also narrow down
+/// by path. When stdout is not a terminal, or with `list`, the matching lines are
+/// printed in that format instead, best match first.
+pub fn grep_notes(vault: &VaultContext, query: &str, list: bool) -> Result<(), Box<dyn Error>> {
+    let lines = index(vault)?;
+    let items: Vec<String> = lines
+        .iter()
+        .map(|l| format!("{}:{}: {}", l.relpath, l.line, l.text))
+        .collect();
+
+    if list || !io::stdout().is_terminal() {
+        for i in filter(&items, query) {
+            println!("{}", items[i]);
+        }
+        return Ok(());
+    }
+
+    if lines.is_empty() {
+        return Err(format!("No notes found in vault '{}'", vault.name).into());
+    }
+    let mut preview = LinePreview::new(vault, &lines);
+    let options = FinderOptions {
+        preview: Some(&mut preview),
+        query: query.to_string(),
+        ..Default::default()
+    };
+    let Some(index) = find(&items, options)? else {
+        return Err("No line selected".into());
+    };
+    let chosen = &lines[index];
+    open_in_editor_at(&vault.dir.join(&chosen.relpath), chosen.line)
+}
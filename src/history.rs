@@ -0,0 +1,306 @@
+// src/history.rs
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::notes::VaultNote;
+use crate::paths::config_dir;
+use crate::vault::VaultContext;
+
+/// File in the configuration directory that note opens are recorded in.
+const HISTORY_FILE: &str = "history.yaml";
+
+/// Number of notes remembered; the ones with the lowest frecency are dropped first.
+const MAX_ENTRIES: usize = 1000;
+
+/// How often and how recently a note was opened.
+#[derive(Serialize, Deserialize)]
+pub struct Visit {
+    pub vault: String,
+    /// Path of the note relative to the vault.
+    pub relpath: String,
+    pub count: u32,
+    /// When the note was last opened, in RFC 3339 format.
+    pub last: String,
+}
+
+impl Visit {
+    fn last_opened(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.last)
+            .ok()
+            .map(|time| time.with_timezone(&Local))
+    }
+
+    /// Scores the visit like zoxide: the open count, weighted by how long ago
+    /// the note was last opened.
+    fn frecency(&self, now: DateTime<Local>) -> f64 {
+        let Some(last) = self.last_opened() else {
+            return 0.0;
+        };
+        let hours = (now - last).num_hours();
+        let weight = match hours {
+            ..1 => 4.0,
+            1..24 => 2.0,
+            24..168 => 0.5,
+            _ => 0.25,
+        };
+        f64::from(self.count) * weight
+    }
+}
+
+fn history_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config_dir()?.join(HISTORY_FILE))
+}
+
+fn load() -> Result<Vec<Visit>, Box<dyn Error>> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save(mut visits: Vec<Visit>) -> Result<(), Box<dyn Error>> {
+    prune(&mut visits, MAX_ENTRIES, Local::now());
+    fs::write(history_path()?, serde_yaml::to_string(&visits)?)?;
+    Ok(())
+}
+
+/// Keeps the `max` visits with the highest frecency.
+fn prune(visits: &mut Vec<Visit>, max: usize, now: DateTime<Local>) {
+    if visits.len() > max {
+        visits.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+        visits.truncate(max);
+    }
+}
+
+/// Counts an open of the note at `relpath` in `vault` at `now`.
+fn add_visit(visits: &mut Vec<Visit>, vault: &str, relpath: String, now: String) {
+    match visits
+        .iter_mut()
+        .find(|v| v.vault == vault && v.relpath == relpath)
+    {
+        Some(visit) => {
+            visit.count += 1;
+            visit.last = now;
+        }
+        None => visits.push(Visit {
+            vault: vault.to_string(),
+            relpath,
+            count: 1,
+            last: now,
+        }),
+    }
+}
+
+/// Records that the note at `path` (absolute, or relative to the vault) was opened.
+///
+/// The history is a convenience, so a failure to update it is reported as a
+/// warning rather than failing the command.
+pub fn record_open(vault: &VaultContext, path: &Path) {
+    let relpath = path.strip_prefix(&vault.dir).unwrap_or(path);
+    let relpath = relpath.to_string_lossy().to_string();
+    let result = load().and_then(|mut visits| {
+        let now = Local::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        add_visit(&mut visits, &vault.name, relpath, now);
+        save(visits)
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: could not update the note history: {}", err);
+    }
+}
+
+/// Points the history entry of a moved note at its new location.
+pub fn record_move(from_vault: &str, from: &str, to_vault: &str, to: &str) {
+    let result = load().and_then(|mut visits| {
+        let Some(visit) = visits
+            .iter_mut()
+            .find(|v| v.vault == from_vault && v.relpath == from)
+        else {
+            return Ok(());
+        };
+        visit.vault = to_vault.to_string();
+        visit.relpath = to.to_string();
+        save(visits)
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: could not update the note history: {}", err);
+    }
+}
+
+/// Sorts `notes` (listed from `vaults`) by frecency, most frecent first. Notes
+/// that were never opened keep their order after the others.
+pub fn rank(vaults: &[VaultContext], notes: &mut [VaultNote]) {
+    let Ok(visits) = load() else {
+        return;
+    };
+    rank_by(&visits, Local::now(), vaults, notes);
+}
+
+fn rank_by(
+    visits: &[Visit],
+    now: DateTime<Local>,
+    vaults: &[VaultContext],
+    notes: &mut [VaultNote],
+) {
+    let scores: HashMap<(&str, &str), f64> = visits
+        .iter()
+        .map(|v| ((v.vault.as_str(), v.relpath.as_str()), v.frecency(now)))
+        .collect();
+    let score = |entry: &VaultNote| {
+        let key = (
+            vaults[entry.vault].name.as_str(),
+            entry.note.relpath.as_str(),
+        );
+        scores.get(&key).copied().unwrap_or(0.0)
+    };
+    notes.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
+/// Implements `ncy recent`: prints the `limit` notes of `vault` opened most
+/// recently, with the time each was last opened.
+pub fn print_recent(vault: &VaultContext, limit: usize) -> Result<(), Box<dyn Error>> {
+    let mut visits: Vec<(DateTime<Local>, Visit)> = load()?
+        .into_iter()
+        .filter(|v| v.vault == vault.name && vault.dir.join(&v.relpath).is_file())
+        .filter_map(|v| Some((v.last_opened()?, v)))
+        .collect();
+    if visits.is_empty() {
+        status!("No notes opened yet in vault '{}'", vault.name);
+        return Ok(());
+    }
+    visits.sort_by_key(|(last, _)| Reverse(*last));
+    for (last, visit) in visits.iter().take(limit) {
+        println!("{}  {}", last.format("%Y-%m-%d %H:%M"), visit.relpath);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::VaultSource;
+    use chrono::TimeDelta;
+    use notemancy_core::utils::NoteInfo;
+
+    fn now() -> DateTime<Local> {
+        DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    fn visit(vault: &str, relpath: &str, count: u32, hours_ago: i64) -> Visit {
+        let last = now() - TimeDelta::hours(hours_ago);
+        Visit {
+            vault: vault.to_string(),
+            relpath: relpath.to_string(),
+            count,
+            last: last.to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+
+    fn vault(name: &str) -> VaultContext {
+        VaultContext {
+            name: name.to_string(),
+            dir: PathBuf::from("/").join(name),
+            source: VaultSource::Flag,
+        }
+    }
+
+    fn note(vault: usize, relpath: &str) -> VaultNote {
+        VaultNote {
+            vault,
+            note: NoteInfo {
+                relpath: relpath.to_string(),
+                title: relpath.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn frecency_weights_the_count_by_recency() {
+        let cases = [
+            (0, 4.0),
+            (1, 2.0),
+            (23, 2.0),
+            (24, 0.5),
+            (167, 0.5),
+            (168, 0.25),
+            (10_000, 0.25),
+        ];
+        for (hours_ago, weight) in cases {
+            let score = visit("v", "a.md", 3, hours_ago).frecency(now());
+            assert_eq!(score, 3.0 * weight, "{hours_ago}h ago");
+        }
+    }
+
+    #[test]
+    fn frecency_of_an_unreadable_time_is_zero() {
+        let mut visit = visit("v", "a.md", 5, 0);
+        visit.last = "yesterday".to_string();
+        assert_eq!(visit.frecency(now()), 0.0);
+    }
+
+    #[test]
+    fn rank_puts_frecent_notes_first_and_keeps_the_rest_in_order() {
+        let vaults = [vault("main"), vault("work")];
+        let visits = [
+            visit("main", "old.md", 10, 500),
+            visit("main", "fresh.md", 1, 0),
+            visit("work", "b.md", 3, 2),
+        ];
+        let mut notes = vec![
+            note(0, "a.md"),
+            note(0, "b.md"),
+            note(0, "old.md"),
+            note(0, "c.md"),
+            note(1, "b.md"),
+            note(0, "fresh.md"),
+        ];
+        rank_by(&visits, now(), &vaults, &mut notes);
+        let order: Vec<(usize, &str)> = notes
+            .iter()
+            .map(|entry| (entry.vault, entry.note.relpath.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (1, "b.md"),
+                (0, "fresh.md"),
+                (0, "old.md"),
+                (0, "a.md"),
+                (0, "b.md"),
+                (0, "c.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_visit_counts_opens_per_vault_and_note() {
+        let mut visits = vec![visit("main", "a.md", 2, 48)];
+        add_visit(&mut visits, "main", "a.md".to_string(), "later".to_string());
+        add_visit(&mut visits, "work", "a.md".to_string(), "later".to_string());
+        assert_eq!(visits.len(), 2);
+        assert_eq!((visits[0].count, visits[0].last.as_str()), (3, "later"));
+        assert_eq!((visits[1].vault.as_str(), visits[1].count), ("work", 1));
+    }
+
+    #[test]
+    fn prune_drops_the_least_frecent_visits() {
+        let mut visits = vec![
+            visit("v", "stale.md", 20, 1000),
+            visit("v", "hot.md", 2, 0),
+            visit("v", "warm.md", 3, 5),
+        ];
+        prune(&mut visits, 3, now());
+        assert_eq!(visits.len(), 3);
+        prune(&mut visits, 2, now());
+        let kept: Vec<&str> = visits.iter().map(|v| v.relpath.as_str()).collect();
+        assert_eq!(kept, ["hot.md", "warm.md"]);
+    }
+}
@@ -0,0 +1,256 @@
+// src/links.rs
+
+use std::path::{Component, Path, PathBuf};
+
+/// A link found in a note, as passed to the callback of `map_links`.
+pub enum Link<'a> {
+    /// The target of a `[[wikilink]]`, without any `#heading` or `|alias`.
+    Wiki(&'a str),
+    /// The destination of a markdown `[text](dest)` link, without any `#anchor`.
+    Markdown(&'a str),
+}
+
+/// Calls `f` for every wikilink and markdown link in `content` outside fenced
+/// code blocks, replacing the link target with the value `f` returns, if any.
+/// Headings, aliases, anchors and link titles are kept as they are.
+pub fn map_links(content: &str, mut f: impl FnMut(Link) -> Option<String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || !(line.contains("[[") || line.contains("](")) {
+            out.push_str(line);
+            continue;
+        }
+        map_line(line, &mut out, &mut f);
+    }
+    out
+}
+
+fn map_line(line: &str, out: &mut String, f: &mut impl FnMut(Link) -> Option<String>) {
+    let mut rest = line;
+    loop {
+        let wiki = rest.find("[[");
+        let markdown = rest.find("](");
+        let (start, is_wiki) = match (wiki, markdown) {
+            (Some(w), Some(m)) if w < m => (w, true),
+            (Some(w), None) => (w, true),
+            (_, Some(m)) => (m, false),
+            (None, None) => break,
+        };
+        let open = start + 2;
+        let close = if is_wiki { "]]" } else { ")" };
+        let Some(len) = rest[open..].find(close) else {
+            break;
+        };
+        let inner = &rest[open..open + len];
+        out.push_str(&rest[..open]);
+
+        // Split the target from the trailing `#heading|alias` or `#anchor "title"`.
+        let target_end = if is_wiki {
+            inner.find(['#', '|'])
+        } else {
+            inner.find(['#', ' '])
+        }
+        .unwrap_or(inner.len());
+        let target = &inner[..target_end];
+        let replacement = if target.is_empty() {
+            None
+        } else if is_wiki {
+            f(Link::Wiki(target))
+        } else {
+            f(Link::Markdown(target))
+        };
+        match replacement {
+            Some(new_target) => {
+                out.push_str(&new_target);
+                out.push_str(&inner[target_end..]);
+            }
+            None => out.push_str(inner),
+        }
+        out.push_str(close);
+        rest = &rest[open + len + close.len()..];
+    }
+    out.push_str(rest);
+}
+
+/// Returns true if a markdown link destination points outside the vault
+/// (a URL or an absolute path).
+pub fn is_external(dest: &str) -> bool {
+    dest.contains("://") || dest.starts_with("mailto:") || dest.starts_with('/')
+}
+
+/// Resolves a relative link destination found in the note at `from` (a path
+/// relative to the vault) to a vault-relative path. Returns `None` if the
+/// destination leaves the vault.
+pub fn resolve(from: &str, dest: &str) -> Option<String> {
+    let base = Path::new(from).parent().unwrap_or(Path::new(""));
+    let mut parts: Vec<String> = Vec::new();
+    for component in base.join(dest).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Returns the destination a markdown link in the note at `from` should use to
+/// point at `to`; both are paths relative to the vault.
+pub fn relative(from: &str, to: &str) -> String {
+    let from_dir: Vec<Component> = Path::new(from)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .components()
+        .collect();
+    let to_parts: Vec<Component> = Path::new(to).components().collect();
+    let common = from_dir
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut path = PathBuf::new();
+    for _ in common..from_dir.len() {
+        path.push("..");
+    }
+    for part in &to_parts[common..] {
+        path.push(part);
+    }
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Returns true if the wikilink `target` refers to the note at `relpath` with
+/// `title`: by path (with or without `.md`), by file name or by title.
+pub fn wiki_matches(target: &str, relpath: &str, title: &str) -> bool {
+    let target = target.trim();
+    let target_path = target.strip_suffix(".md").unwrap_or(target);
+    let note_path = relpath.strip_suffix(".md").unwrap_or(relpath);
+    if target_path.contains('/') {
+        return target_path == note_path;
+    }
+    let stem = Path::new(note_path)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let wanted = target_path.to_lowercase();
+    wanted == stem.to_lowercase() || target.to_lowercase() == title.trim().to_lowercase()
+}
+
+/// Returns true if `content`, the note at `from`, links to the note at `relpath`.
+pub fn links_to(content: &str, from: &str, relpath: &str, title: &str) -> bool {
+    let mut found = false;
+    map_links(content, |link| {
+        found |= match link {
+            Link::Wiki(target) => wiki_matches(target, relpath, title),
+            Link::Markdown(dest) => {
+                !is_external(dest) && resolve(from, dest).as_deref() == Some(relpath)
+            }
+        };
+        None
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upper(content: &str) -> String {
+        map_links(content, |link| match link {
+            Link::Wiki(target) | Link::Markdown(target) => Some(target.to_uppercase()),
+        })
+    }
+
+    #[test]
+    fn map_links_replaces_only_the_target() {
+        assert_eq!(
+            upper("[[a#h|alias]] and [t](b.md#x \"title\")"),
+            "[[A#h|alias]] and [t](B.MD#x \"title\")"
+        );
+        assert_eq!(upper("[[a]][[b]]"), "[[A]][[B]]");
+        assert_eq!(
+            upper("[[#heading]] [t](#anchor)"),
+            "[[#heading]] [t](#anchor)"
+        );
+    }
+
+    #[test]
+    fn map_links_skips_fenced_code_and_unclosed_links() {
+        let content = "```\n[[a]]\n```\n~~~\n[t](b)\n~~~\n[[c]] [[open\n";
+        assert_eq!(
+            upper(content),
+            "```\n[[a]]\n```\n~~~\n[t](b)\n~~~\n[[C]] [[open\n"
+        );
+    }
+
+    #[test]
+    fn resolve_follows_relative_paths_within_the_vault() {
+        assert_eq!(resolve("a.md", "b.md").as_deref(), Some("b.md"));
+        assert_eq!(resolve("dir/a.md", "b.md").as_deref(), Some("dir/b.md"));
+        assert_eq!(resolve("dir/a.md", "../b.md").as_deref(), Some("b.md"));
+        assert_eq!(
+            resolve("dir/a.md", "./sub/../c.md").as_deref(),
+            Some("dir/c.md")
+        );
+        assert_eq!(resolve("a.md", "../outside.md"), None);
+        assert_eq!(resolve("a.md", "/abs.md"), None);
+    }
+
+    #[test]
+    fn relative_is_the_inverse_of_resolve() {
+        let cases = [
+            ("a.md", "b.md", "b.md"),
+            ("a.md", "dir/b.md", "dir/b.md"),
+            ("dir/a.md", "b.md", "../b.md"),
+            ("dir/a.md", "dir/b.md", "b.md"),
+            ("x/y/a.md", "x/z/b.md", "../z/b.md"),
+        ];
+        for (from, to, dest) in cases {
+            assert_eq!(relative(from, to), dest);
+            assert_eq!(resolve(from, dest).as_deref(), Some(to));
+        }
+    }
+
+    #[test]
+    fn wiki_matches_by_path_stem_or_title() {
+        assert!(wiki_matches("dir/note", "dir/note.md", "Title"));
+        assert!(wiki_matches("dir/note.md", "dir/note.md", "Title"));
+        assert!(!wiki_matches("other/note", "dir/note.md", "Title"));
+        assert!(wiki_matches("note", "dir/note.md", "Title"));
+        assert!(wiki_matches(" Note ", "dir/note.md", "Title"));
+        assert!(wiki_matches("title", "dir/note.md", "Title"));
+        assert!(wiki_matches("ÉTÉ", "été.md", "x"));
+        assert!(!wiki_matches("notes", "dir/note.md", "Title"));
+    }
+
+    #[test]
+    fn links_to_ignores_external_links() {
+        assert!(links_to("see [[note]]", "a.md", "dir/note.md", "T"));
+        assert!(links_to("see [n](dir/note.md)", "a.md", "dir/note.md", "T"));
+        assert!(links_to(
+            "see [n](../dir/note.md)",
+            "x/a.md",
+            "dir/note.md",
+            "T"
+        ));
+        assert!(!links_to(
+            "see [n](https://x.y/dir/note.md)",
+            "a.md",
+            "dir/note.md",
+            "T"
+        ));
+        assert!(!links_to(
+            "```\n[[note]]\n```\n",
+            "a.md",
+            "dir/note.md",
+            "T"
+        ));
+    }
+}
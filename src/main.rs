@@ -1,136 +1,299 @@
 // src/main.rs
 
+/// Prints an informational line to stdout unless `--quiet` was given.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::cli::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod bm25;
+mod chunk;
+mod cli;
+mod completions;
 mod config;
 mod crud;
+mod daily;
 mod edit;
+mod finder;
+mod frontmatter;
+mod grep;
+mod history;
+mod links;
+mod mv;
+mod notes;
+mod paths;
 mod picker;
+mod preview;
 mod publish; // new publish module
+mod query;
+mod related;
+mod schema;
+mod search;
+mod setup;
+mod store;
+mod templates;
+mod trash;
+mod vault;
 mod vectorize;
 
+use clap::Parser;
+use cli::{Cli, Command, ConfigCommand, EXIT_FAILURE, TrashCommand, VaultCommand};
 use config::{init_config, set_default_vault};
-use crud::new_note;
-use edit::edit_note;
+use crud::{NewNoteOptions, new_note};
+use daily::{Period, open_periodic, today};
+use edit::{EditOptions, edit_note};
 use notemancy_core::config::get_vault_dir;
+use notemancy_core::utils::NoteInfo;
 use picker::pick_note;
-use std::env;
-use std::fs;
+use std::error::Error;
 use std::process;
+use vault::{VaultContext, resolve_vault, resolve_vaults};
 
-fn get_default_vault() -> Result<String, Box<dyn std::error::Error>> {
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")?;
-    let default_path = std::path::Path::new(&conf_dir).join("default_vault.txt");
-    if default_path.exists() {
-        let vault_name = fs::read_to_string(default_path)?;
-        let trimmed = vault_name.trim().to_string();
-        if trimmed.is_empty() {
-            return Err("No default vault set".into());
-        }
-        Ok(trimmed)
-    } else {
-        Err("No default vault set".into())
+/// Runs `future` to completion on a new tokio runtime.
+fn block_on<F: Future>(future: F) -> F::Output {
+    match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt.block_on(future),
+        Err(err) => fail("Error creating async runtime", err.into()),
+    }
+}
+
+/// Finds the note named by `query` (see `notes::find_note`), or lets the user
+/// pick one when no note was given.
+fn note_or_pick(vault: &VaultContext, query: Option<&str>) -> Result<NoteInfo, Box<dyn Error>> {
+    match query {
+        Some(query) => notes::find_note(&vault.name, query),
+        None => picker::select_note(vault),
+    }
+}
+
+/// Reports a failed command on stderr and exits with `EXIT_FAILURE`.
+fn fail(context: &str, err: Box<dyn Error>) -> ! {
+    eprintln!("{}: {}", context, err);
+    process::exit(EXIT_FAILURE);
+}
+
+/// Resolves the vault a command operates on, exiting if none can be determined.
+fn vault_or_exit(flag: Option<String>) -> VaultContext {
+    match resolve_vault(flag.as_deref()) {
+        Ok(vault) => vault,
+        Err(err) => fail("Error selecting vault", err),
+    }
+}
+
+/// Resolves the vaults for commands that accept `@*`, exiting if none can be determined.
+fn vaults_or_exit(flag: Option<String>, all: bool) -> Vec<VaultContext> {
+    match resolve_vaults(flag.as_deref(), all) {
+        Ok(vaults) => vaults,
+        Err(err) => fail("Error selecting vault", err),
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+    cli::set_quiet(cli.quiet);
+
+    // The core library reads its configuration from NOTEMANCY_CONF_DIR, so the
+    // resolved directory (--config-dir, the variable itself or the XDG fallback)
+    // is exported before anything else runs. If no directory can be determined,
+    // commands that need one report it themselves.
+    let _ = paths::export_config_dir(cli.config_dir.as_deref());
 
-    // If no command is provided, run the nucleo-picker based note selector.
-    if args.len() == 1 {
-        if let Err(err) = pick_note() {
-            eprintln!("Error picking note: {}", err);
-            process::exit(1);
+    // If no command is provided, run the note picker.
+    let Some(command) = cli.command else {
+        if let Err(err) = pick_note(&vaults_or_exit(cli.vault, cli.all_vaults)) {
+            fail("Error picking note", err);
         }
         return;
-    }
+    };
 
-    match args[1].as_str() {
-        "init" => {
-            if let Err(err) = init_config() {
-                eprintln!("Error initializing notemancy: {}", err);
-                process::exit(1);
+    match command {
+        Command::Init { edit } => {
+            if let Err(err) = init_config(edit) {
+                fail("Error initializing notemancy", err);
+            }
+        }
+        Command::Config { command } => match command {
+            ConfigCommand::Check => {
+                if let Err(err) = schema::check_config() {
+                    fail("Error checking config", err);
+                }
             }
+            ConfigCommand::Path => {
+                if let Err(err) = paths::print_paths() {
+                    fail("Error resolving paths", err);
+                }
+            }
+        },
+        Command::Set { vault } => {
+            if let Err(err) = set_default_vault(&vault) {
+                fail("Error setting default vault", err);
+            }
+            status!("Default vault set to {}", vault);
         }
-        "set" => {
-            if args.len() < 3 {
-                eprintln!("Usage: notemancy set <vault_name>");
-                process::exit(1);
+        Command::New {
+            title,
+            project,
+            tags,
+            template,
+            if_exists,
+            no_edit,
+        } => {
+            let options = NewNoteOptions {
+                title,
+                project,
+                tags,
+                template,
+                if_exists,
+                no_edit,
+            };
+            if let Err(err) = new_note(&vault_or_exit(cli.vault), options) {
+                fail("Error creating new note", err);
             }
-            let vault_name = &args[2];
-            if let Err(err) = set_default_vault(vault_name) {
-                eprintln!("Error setting default vault: {}", err);
-                process::exit(1);
+        }
+        Command::Today { options } => {
+            let vault = vault_or_exit(cli.vault);
+            if let Err(err) = open_periodic(&vault, Period::Day, today(), options.into()) {
+                fail("Error opening daily note", err);
             }
-            println!("Default vault set to {}", vault_name);
         }
-        "n" => {
-            if let Err(err) = new_note() {
-                eprintln!("Error creating new note: {}", err);
-                process::exit(1);
+        Command::Yesterday { options } => {
+            let vault = vault_or_exit(cli.vault);
+            let date = today().pred_opt().unwrap_or_else(today);
+            if let Err(err) = open_periodic(&vault, Period::Day, date, options.into()) {
+                fail("Error opening daily note", err);
             }
         }
-        "-e" => {
-            let vault_arg = if args.len() >= 3 && args[2].starts_with('@') {
-                Some(args[2].trim_start_matches('@').to_string())
-            } else {
-                None
+        Command::Week { options } => {
+            let vault = vault_or_exit(cli.vault);
+            if let Err(err) = open_periodic(&vault, Period::Week, today(), options.into()) {
+                fail("Error opening weekly note", err);
+            }
+        }
+        Command::Journal { date, options } => {
+            let vault = vault_or_exit(cli.vault);
+            if let Err(err) = open_periodic(&vault, Period::Day, date, options.into()) {
+                fail("Error opening daily note", err);
+            }
+        }
+        Command::Edit {
+            vault,
+            print_path,
+            open,
+            fzf,
+        } => {
+            let options = EditOptions {
+                print_path,
+                open,
+                fzf,
             };
-            if let Err(err) = edit_note(vault_arg) {
-                eprintln!("Error editing note: {}", err);
-                process::exit(1);
-            }
-        }
-        "cd" => {
-            if args.len() < 3 {
-                eprintln!("Usage: notemancy cd <vault_name>");
-                process::exit(1);
-            }
-            let vault_name = &args[2];
-            match get_vault_dir(vault_name) {
-                Ok(dir) => println!("{}", dir),
-                Err(err) => {
-                    eprintln!("Error: {}", err);
-                    process::exit(1);
-                }
+            if let Err(err) = edit_note(&vaults_or_exit(vault.or(cli.vault), false), options) {
+                fail("Error editing note", err);
             }
         }
-        "vectorize" => {
-            let vault = if args.len() >= 3 {
-                args[2].clone()
-            } else {
-                match get_default_vault() {
-                    Ok(vault) => vault,
-                    Err(err) => {
-                        eprintln!(
-                            "Error: {}; please specify a vault with 'notemancy vectorize <vault_name>'",
-                            err
-                        );
-                        process::exit(1);
-                    }
-                }
+        Command::Mv { note, destination } => {
+            let vault = vault_or_exit(cli.vault);
+            if let Err(err) = block_on(mv::move_note(&vault, &note, destination)) {
+                fail("Error moving note", err);
+            }
+        }
+        Command::Rm { note } => {
+            let vault = vault_or_exit(cli.vault);
+            let result = note_or_pick(&vault, note.as_deref())
+                .and_then(|note| block_on(trash::trash_note(&vault, &note)));
+            if let Err(err) = result {
+                fail("Error deleting note", err);
+            }
+        }
+        Command::Archive { note } => {
+            let vault = vault_or_exit(cli.vault);
+            let result = note_or_pick(&vault, note.as_deref())
+                .and_then(|note| block_on(mv::archive_note(&vault, &note)));
+            if let Err(err) = result {
+                fail("Error archiving note", err);
+            }
+        }
+        Command::Trash { command } => {
+            let vault = vault_or_exit(cli.vault);
+            let result = match command {
+                TrashCommand::List => trash::list_trash(&vault),
+                TrashCommand::Restore { id } => trash::restore_note(&vault, &id),
+                TrashCommand::Empty { yes } => trash::empty_trash(&vault, yes),
             };
-
-            let rt = match tokio::runtime::Runtime::new() {
-                Ok(rt) => rt,
-                Err(err) => {
-                    eprintln!("Error creating async runtime: {}", err);
-                    process::exit(1);
+            if let Err(err) = result {
+                fail("Error managing trash", err);
+            }
+        }
+        Command::Recent { limit } => {
+            if let Err(err) = history::print_recent(&vault_or_exit(cli.vault), limit) {
+                fail("Error listing recent notes", err);
+            }
+        }
+        Command::Grep { query, list } => {
+            if let Err(err) = grep::grep_notes(&vault_or_exit(cli.vault), &query, list) {
+                fail("Error searching notes", err);
+            }
+        }
+        Command::Ask {
+            query,
+            top_k,
+            list,
+            mode,
+        } => {
+            if let Err(err) = search::ask(&vault_or_exit(cli.vault), &query, top_k, list, mode) {
+                fail("Error searching notes", err);
+            }
+        }
+        Command::Related {
+            note,
+            top_k,
+            list,
+            write,
+        } => {
+            let vault = vault_or_exit(cli.vault);
+            let result = note_or_pick(&vault, note.as_deref())
+                .and_then(|note| related::related(&vault, &note, top_k, list, write));
+            if let Err(err) = result {
+                fail("Error finding related notes", err);
+            }
+        }
+        Command::Vault { command } => {
+            let result = match command {
+                VaultCommand::List => vault::list_vaults(),
+                VaultCommand::Add { name, path } => vault::add_vault(&name, &path),
+                VaultCommand::Remove { name } => vault::remove_vault(&name),
+                VaultCommand::Rename { old, new } => vault::rename_vault(&old, &new),
+                VaultCommand::Show { name } => {
+                    vault::show_vault(&vault_or_exit(name.or(cli.vault)))
                 }
             };
+            if let Err(err) = result {
+                fail("Error managing vaults", err);
+            }
+        }
+        Command::Cd { vault } => match get_vault_dir(&vault) {
+            Ok(dir) => println!("{}", dir),
+            Err(err) => fail("Error", err),
+        },
+        Command::Vectorize { vault, full } => {
+            let vault = vault_or_exit(vault.or(cli.vault));
 
-            if let Err(err) = rt.block_on(vectorize::vectorize_vault(&vault)) {
-                eprintln!("Error vectorizing vault: {}", err);
-                process::exit(1);
+            if let Err(err) = block_on(vectorize::vectorize_vault(&vault, full)) {
+                fail("Error vectorizing vault", err);
             }
         }
-        "publish" => {
-            if let Err(err) = publish::publish_notes() {
-                eprintln!("Error publishing notes: {}", err);
-                process::exit(1);
+        Command::Publish => {
+            if let Err(err) = publish::publish_notes(&vault_or_exit(cli.vault)) {
+                fail("Error publishing notes", err);
             }
         }
-        _ => {
-            eprintln!("Unknown command: {}. Usage: notemancy <command>", args[1]);
-            process::exit(1);
+        Command::Completions { shell } => completions::print_completions(shell),
+        Command::Complete { kind } => {
+            let vault = resolve_vault(cli.vault.as_deref()).ok();
+            // Errors are swallowed so that a broken config never garbles the shell prompt.
+            let _ = completions::print_candidates(kind, vault.as_ref());
         }
     }
 }
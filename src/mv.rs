@@ -0,0 +1,494 @@
+// src/mv.rs
+
+use chrono::{Local, SecondsFormat};
+use notemancy_core::utils::NoteInfo;
+use std::error::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::bm25::Bm25Index;
+use crate::chunk::rebase_id;
+use crate::config::yaml_scalar;
+use crate::crud::{sanitize_title, write_new_note};
+use crate::frontmatter;
+use crate::history;
+use crate::links::{self, Link};
+use crate::notes::{backlinks, find_note, list_notes};
+use crate::store::{
+    Manifest, load_vault_store, note_ids, rename_note, save_vault_store, store_name,
+};
+use crate::vault::{VaultContext, resolve_vault};
+
+/// Where `ncy mv` moves a note, parsed from its second argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// `New title`: rename the note within its folder.
+    Title(String),
+    /// `project/`: move the note into another folder of the vault; `/` is the vault root.
+    Project(String),
+    /// `@vault`: move the note to the same path in another vault.
+    Vault(String),
+}
+
+impl Destination {
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        if let Some(vault) = arg.strip_prefix('@') {
+            if vault.is_empty() {
+                return Err("vault name must not be empty".to_string());
+            }
+            return Ok(Destination::Vault(vault.to_string()));
+        }
+        if arg.ends_with('/') {
+            return Destination::project(arg);
+        }
+        if arg.trim().is_empty() {
+            return Err("the new title must not be empty".to_string());
+        }
+        Ok(Destination::Title(arg.to_string()))
+    }
+
+    /// Parses the folder `folder` of the vault as a destination; leading and
+    /// trailing slashes are ignored. Folders that would leave the vault, such
+    /// as `../elsewhere`, are rejected.
+    pub fn project(folder: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        for component in Path::new(folder.trim_matches('/')).components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy()),
+                Component::CurDir => {}
+                _ => return Err(format!("'{}' is not a folder inside the vault", folder)),
+            }
+        }
+        Ok(Destination::Project(parts.join("/")))
+    }
+}
+
+/// Directory inside a vault that `ncy archive` moves notes into.
+const ARCHIVE_DIR: &str = "archive";
+
+/// Implements `ncy mv`: renames or moves the note `query` (see `find_note`) to
+/// `destination`.
+pub async fn move_note(
+    vault: &VaultContext,
+    query: &str,
+    destination: Destination,
+) -> Result<(), Box<dyn Error>> {
+    let note = find_note(&vault.name, query)?;
+    let old_relpath = Path::new(&note.relpath);
+    let file_name = old_relpath
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let folder = old_relpath
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let plan = match destination {
+        Destination::Title(title) => Move {
+            target: None,
+            relpath: join(&folder, &format!("{}.md", sanitize_title(&title))),
+            title: Some(title),
+        },
+        Destination::Project(project) => Move {
+            target: None,
+            relpath: join(&project, &file_name),
+            title: None,
+        },
+        Destination::Vault(name) => {
+            let target = resolve_vault(Some(&name))?;
+            if target.name == vault.name {
+                return Err(format!("The note is already in vault '{}'", vault.name).into());
+            }
+            Move {
+                target: Some(target),
+                relpath: note.relpath.clone(),
+                title: None,
+            }
+        }
+    };
+
+    let new_path = relocate(vault, &note, plan, |content| content).await?;
+    println!("{}", new_path.display());
+    Ok(())
+}
+
+/// Implements `ncy archive`: moves `note` to the same path under `archive/`,
+/// recording when and from where it was archived in its frontmatter. Links to
+/// the note are rewritten as for `ncy mv`.
+pub async fn archive_note(vault: &VaultContext, note: &NoteInfo) -> Result<(), Box<dyn Error>> {
+    if Path::new(&note.relpath).starts_with(ARCHIVE_DIR) {
+        return Err(format!("{} is already archived", note.relpath).into());
+    }
+    let plan = Move {
+        target: None,
+        relpath: join(ARCHIVE_DIR, &note.relpath),
+        title: None,
+    };
+    let archived = Local::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let new_path = relocate(vault, note, plan, |content| {
+        let content = frontmatter::set_field(&content, "archived", &yaml_scalar(&archived));
+        frontmatter::set_field(&content, "archived_from", &yaml_scalar(&note.relpath))
+    })
+    .await?;
+    println!("{}", new_path.display());
+    Ok(())
+}
+
+/// Joins a vault-relative folder and a file name with '/'.
+fn join(folder: &str, file: &str) -> String {
+    if folder.is_empty() {
+        file.to_string()
+    } else {
+        format!("{}/{}", folder, file)
+    }
+}
+
+/// Where `relocate` moves a note.
+struct Move {
+    /// The vault to move the note to, if not its own.
+    target: Option<VaultContext>,
+    /// The new path of the note, relative to the target vault.
+    relpath: String,
+    /// The new title of the note, if it changes.
+    title: Option<String>,
+}
+
+/// Moves `note` as described by `plan`, passing its content through `edit` on
+/// the way, and returns the new path.
+///
+/// A renamed note gets its new title in the frontmatter. Within a vault every
+/// wikilink and relative markdown link pointing at the note is rewritten, as are
+/// the relative links inside a note that changes folder. The note's entry in the
+/// vault's vector store is re-keyed to the new path, or moved to the other
+/// vault's store.
+async fn relocate(
+    vault: &VaultContext,
+    note: &NoteInfo,
+    plan: Move,
+    edit: impl FnOnce(String) -> String,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let old_relpath = &note.relpath;
+    let new_relpath = &plan.relpath;
+    let target_dir = plan.target.as_ref().map_or(&vault.dir, |t| &t.dir);
+    let old_path = vault.dir.join(old_relpath);
+    let new_path = target_dir.join(new_relpath);
+    if plan.target.is_none() && new_relpath == old_relpath {
+        return Err(format!("{} is already at that location", old_relpath).into());
+    }
+    if new_path.exists() {
+        return Err(format!("A note already exists at {}", new_path.display()).into());
+    }
+
+    let mut content = fs::read_to_string(&old_path)?;
+    if let Some(title) = &plan.title {
+        content = frontmatter::set_field(&content, "title", &yaml_scalar(title));
+    }
+    if plan.target.is_none() {
+        content = rebase_links(&content, old_relpath, new_relpath);
+    }
+    write_new_note(&new_path, &edit(content))?;
+    fs::remove_file(&old_path)?;
+    status!("Moved {} to {}", old_path.display(), new_path.display());
+    let target_vault = plan.target.as_ref().unwrap_or(vault);
+    history::record_move(&vault.name, old_relpath, &target_vault.name, new_relpath);
+
+    if plan.target.is_none() {
+        let new_title = plan.title.as_deref().unwrap_or(&note.title);
+        match rewrite_backlinks(vault, old_relpath, &note.title, new_relpath, new_title) {
+            Ok(0) => {}
+            Ok(updated) => status!("Updated links in {} note(s)", updated),
+            Err(e) => eprintln!("Warning: could not update links to the moved note: {}", e),
+        }
+    } else {
+        let linking = backlinks(vault, note).map_or(0, |notes| notes.len());
+        if linking > 0 {
+            eprintln!(
+                "Warning: {} note(s) in vault '{}' still link to the moved note",
+                linking, vault.name
+            );
+        }
+    }
+
+    update_store(vault, plan.target.as_ref(), old_relpath, new_relpath).await?;
+    Ok(new_path)
+}
+
+/// Rewrites the relative markdown links in `content`, the note moving from
+/// `old_relpath` to `new_relpath`, so that they still point at the same notes.
+fn rebase_links(content: &str, old_relpath: &str, new_relpath: &str) -> String {
+    links::map_links(content, |link| match link {
+        Link::Markdown(dest) if !links::is_external(dest) => {
+            let resolved = links::resolve(old_relpath, dest)?;
+            // A link to the note itself follows it to its new path.
+            let resolved = if resolved == old_relpath {
+                new_relpath.to_string()
+            } else {
+                resolved
+            };
+            let rebased = links::relative(new_relpath, &resolved);
+            (rebased != dest).then_some(rebased)
+        }
+        _ => None,
+    })
+}
+
+/// Points the links to `old_relpath` in the notes of `vault` at
+/// `new_relpath` and returns the number of notes changed.
+fn rewrite_backlinks(
+    vault: &VaultContext,
+    old_relpath: &str,
+    old_title: &str,
+    new_relpath: &str,
+    new_title: &str,
+) -> Result<usize, Box<dyn Error>> {
+    let mut updated = 0;
+    // The moved note itself is included, so its wikilinks to itself are updated too.
+    for other in list_notes(&vault.name)? {
+        let path = vault.dir.join(&other.relpath);
+        // The note has already moved, so one unreadable note must not stop the
+        // others from being updated.
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: could not read {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let rewritten = rewrite_links(
+            &content,
+            &other.relpath,
+            old_relpath,
+            old_title,
+            new_relpath,
+            new_title,
+        );
+        if rewritten != content {
+            match fs::write(&path, rewritten) {
+                Ok(()) => updated += 1,
+                Err(e) => eprintln!(
+                    "Warning: could not update links in {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+    Ok(updated)
+}
+
+/// Points the links to `old_relpath` in `content`, the note at `from`, at
+/// `new_relpath`. Wikilinks keep their style: by path, by file name or by title.
+fn rewrite_links(
+    content: &str,
+    from: &str,
+    old_relpath: &str,
+    old_title: &str,
+    new_relpath: &str,
+    new_title: &str,
+) -> String {
+    let old_stem = stem(old_relpath);
+    let new_stem = stem(new_relpath);
+    links::map_links(content, |link| match link {
+        Link::Wiki(target) if links::wiki_matches(target, old_relpath, old_title) => {
+            let trimmed = target.trim();
+            let bare = trimmed.strip_suffix(".md").unwrap_or(trimmed);
+            let new_target = if bare.contains('/') {
+                new_relpath.trim_end_matches(".md").to_string()
+            } else if bare.to_lowercase() == old_stem.to_lowercase() {
+                new_stem.clone()
+            } else {
+                // Linked by title.
+                new_title.to_string()
+            };
+            Some(if trimmed.ends_with(".md") {
+                format!("{}.md", new_target)
+            } else {
+                new_target
+            })
+        }
+        Link::Markdown(dest)
+            if !links::is_external(dest)
+                && links::resolve(from, dest).as_deref() == Some(old_relpath) =>
+        {
+            Some(links::relative(from, new_relpath))
+        }
+        _ => None,
+    })
+}
+
+fn stem(relpath: &str) -> String {
+    Path::new(relpath)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Re-keys the vectors and BM25 index entries of a moved note, or moves its
+/// vectors to the store of `target`. Vaults that have not been vectorized are
+/// left alone.
+async fn update_store(
+    vault: &VaultContext,
+    target: Option<&VaultContext>,
+    old_relpath: &str,
+    new_relpath: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(mut index) = Bm25Index::load(&vault.name)? {
+        match target {
+            None => index.rename_note(old_relpath, new_relpath),
+            Some(_) => index.remove_note(old_relpath),
+        }
+        index.save(&vault.name)?;
+    }
+
+    let Some(mut store) = load_vault_store(&vault.name)? else {
+        return Ok(());
+    };
+    let ids = note_ids(&store, old_relpath);
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    match target {
+        None => {
+            rename_note(&mut store, old_relpath, new_relpath);
+            save_vault_store(&vault.name, &store).await?;
+            let mut manifest = Manifest::load(&vault.name)?;
+            if let Some(entry) = manifest.notes.remove(old_relpath) {
+                manifest.notes.insert(new_relpath.to_string(), entry);
+                manifest.save(&vault.name)?;
+            }
+        }
+        Some(target) => {
+            // The target's manifest has no entry for the note, so the next
+            // 'ncy vectorize' of that vault embeds it again.
+            if let Some(mut target_store) = load_vault_store(&target.name)? {
+                let vectors: Vec<_> = ids
+                    .iter()
+                    .filter_map(|id| {
+                        let vector = store.get_vector(Some(id), None).ok()?;
+                        Some((rebase_id(id, new_relpath), vector))
+                    })
+                    .collect();
+                target_store
+                    .add_vectors(&vectors, &store_name(&target.name))
+                    .map_err(|e| format!("Failed to update vector store: {}", e))?;
+                save_vault_store(&target.name, &target_store).await?;
+            }
+            store
+                .delete_vectors(&ids, &store_name(&vault.name))
+                .map_err(|e| format!("Failed to update vector store: {}", e))?;
+            save_vault_store(&vault.name, &store).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(content: &str, from: &str) -> String {
+        rewrite_links(
+            content,
+            from,
+            "work/old.md",
+            "Old Note",
+            "ideas/new.md",
+            "New Note",
+        )
+    }
+
+    #[test]
+    fn wikilinks_keep_their_style() {
+        assert_eq!(rewrite("[[old]]", "a.md"), "[[new]]");
+        assert_eq!(rewrite("[[old.md]]", "a.md"), "[[new.md]]");
+        assert_eq!(rewrite("[[work/old]]", "a.md"), "[[ideas/new]]");
+        assert_eq!(rewrite("[[work/old.md]]", "a.md"), "[[ideas/new.md]]");
+        assert_eq!(rewrite("[[Old Note]]", "a.md"), "[[New Note]]");
+        assert_eq!(rewrite("[[OLD]]", "a.md"), "[[new]]");
+    }
+
+    #[test]
+    fn wikilinks_keep_headings_and_aliases() {
+        assert_eq!(
+            rewrite("see [[old#Plan|the plan]] and [[old|it]]", "a.md"),
+            "see [[new#Plan|the plan]] and [[new|it]]"
+        );
+    }
+
+    #[test]
+    fn markdown_links_are_made_relative_to_the_linking_note() {
+        assert_eq!(rewrite("[x](work/old.md)", "a.md"), "[x](ideas/new.md)");
+        assert_eq!(
+            rewrite("[x](old.md#top)", "work/b.md"),
+            "[x](../ideas/new.md#top)"
+        );
+        assert_eq!(rewrite("[x](../work/old.md)", "ideas/c.md"), "[x](new.md)");
+    }
+
+    #[test]
+    fn other_links_and_code_blocks_are_left_alone() {
+        let content = "[[other]] [[work/older]] [x](old.md) [y](https://e.com/work/old.md)\n\
+                       ```\n[[old]]\n```\n";
+        assert_eq!(rewrite(content, "a.md"), content);
+    }
+
+    #[test]
+    fn moved_notes_keep_their_relative_links_working() {
+        assert_eq!(
+            rebase_links(
+                "[a](a.md) [s](sub/s.md) [w](https://x.y/a.md)",
+                "n.md",
+                "deep/dir/n.md"
+            ),
+            "[a](../../a.md) [s](../../sub/s.md) [w](https://x.y/a.md)"
+        );
+        assert_eq!(
+            rebase_links("[me](n.md#top) [[a]]", "dir/n.md", "n.md"),
+            "[me](n.md#top) [[a]]"
+        );
+        assert_eq!(
+            rebase_links("[up](../a.md)", "dir/n.md", "other/n.md"),
+            "[up](../a.md)"
+        );
+    }
+
+    #[test]
+    fn destinations_are_titles_projects_or_vaults() {
+        assert_eq!(
+            Destination::parse("New title"),
+            Ok(Destination::Title("New title".to_string()))
+        );
+        assert_eq!(
+            Destination::parse("work/ideas/"),
+            Ok(Destination::Project("work/ideas".to_string()))
+        );
+        assert_eq!(
+            Destination::parse("/"),
+            Ok(Destination::Project(String::new()))
+        );
+        assert_eq!(
+            Destination::parse("@archive"),
+            Ok(Destination::Vault("archive".to_string()))
+        );
+        assert_eq!(
+            Destination::project(""),
+            Ok(Destination::Project(String::new()))
+        );
+        assert!(Destination::parse("@").is_err());
+        assert!(Destination::parse("  ").is_err());
+    }
+
+    #[test]
+    fn destinations_outside_the_vault_are_rejected() {
+        assert!(Destination::parse("../elsewhere/").is_err());
+        assert!(Destination::parse("work/../../elsewhere/").is_err());
+        assert!(Destination::parse("work/../").is_err());
+        assert!(Destination::project("..").is_err());
+        assert_eq!(
+            Destination::parse("./work/./ideas/"),
+            Ok(Destination::Project("work/ideas".to_string()))
+        );
+    }
+}
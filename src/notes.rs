@@ -0,0 +1,176 @@
+// src/notes.rs
+
+use notemancy_core::utils::NoteInfo;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::links::{self, Link};
+use crate::vault::VaultContext;
+
+/// A note listed from several vaults at once, with the index of its vault.
+#[derive(Clone)]
+pub struct VaultNote {
+    pub vault: usize,
+    pub note: NoteInfo,
+}
+
+/// Lists the notes of every vault in `vaults`. A vault that cannot be listed
+/// is skipped with a warning when there are others to show.
+pub fn list_vault_notes(vaults: &[VaultContext]) -> Result<Vec<VaultNote>, Box<dyn Error>> {
+    let mut all = Vec::new();
+    for (index, vault) in vaults.iter().enumerate() {
+        let notes = match list_notes(&vault.name) {
+            Ok(notes) => notes,
+            Err(err) if vaults.len() > 1 => {
+                eprintln!("Warning: skipping vault '{}': {}", vault.name, err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        all.extend(
+            notes
+                .into_iter()
+                .map(|note| VaultNote { vault: index, note }),
+        );
+    }
+    Ok(all)
+}
+
+/// Lists the notes of a vault like the core's `list_notes`, but skips anything
+/// inside a hidden directory (such as `.templates`), which holds notemancy's own
+/// files rather than notes.
+pub fn list_notes(vault_name: &str) -> Result<Vec<NoteInfo>, Box<dyn Error>> {
+    let notes = notemancy_core::utils::list_notes(vault_name)?;
+    Ok(notes
+        .into_iter()
+        .filter(|note| !is_hidden(&note.relpath))
+        .collect())
+}
+
+/// Returns true if any directory component of `relpath` starts with '.'.
+fn is_hidden(relpath: &str) -> bool {
+    let path = Path::new(relpath);
+    path.parent().is_some_and(|parent| {
+        parent
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+/// Finds the note in `vault_name` that `query` refers to: a path relative to the
+/// vault (the `.md` extension is optional), a file name, or a title. File name
+/// and title matches ignore case; if several notes match, the error lists them.
+pub fn find_note(vault_name: &str, query: &str) -> Result<NoteInfo, Box<dyn Error>> {
+    let notes = list_notes(vault_name)?;
+    let query = query.trim().trim_start_matches("./");
+    let with_ext = if query.ends_with(".md") {
+        query.to_string()
+    } else {
+        format!("{}.md", query)
+    };
+
+    if let Some(pos) = notes.iter().position(|note| note.relpath == with_ext) {
+        return Ok(notes.into_iter().nth(pos).expect("position is in bounds"));
+    }
+
+    let stem_of = |relpath: &str| {
+        Path::new(relpath)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    };
+    let wanted = query.trim_end_matches(".md").to_lowercase();
+    let mut matches: Vec<NoteInfo> = notes
+        .into_iter()
+        .filter(|note| stem_of(&note.relpath) == wanted || note.title.to_lowercase() == wanted)
+        .collect();
+
+    match matches.len() {
+        0 => Err(format!("No note matching '{}' in vault '{}'", query, vault_name).into()),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let paths: Vec<&str> = matches.iter().map(|note| note.relpath.as_str()).collect();
+            Err(format!(
+                "'{}' matches several notes; use a path instead:\n  {}",
+                query,
+                paths.join("\n  ")
+            )
+            .into())
+        }
+    }
+}
+
+/// Returns the paths of the other notes in `vault` that link to `note`.
+pub fn backlinks(vault: &VaultContext, note: &NoteInfo) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut linking = Vec::new();
+    for other in list_notes(&vault.name)? {
+        if other.relpath == note.relpath {
+            continue;
+        }
+        let content = fs::read_to_string(vault.dir.join(&other.relpath))?;
+        if links::links_to(&content, &other.relpath, &note.relpath, &note.title) {
+            linking.push(other.relpath);
+        }
+    }
+    Ok(linking)
+}
+
+/// Counts, for every note of `vault`, how many other notes link to it.
+///
+/// Reads each note once, so it is far cheaper than calling `backlinks` for
+/// every note. Notes without incoming links are not in the map.
+pub fn backlink_counts(vault: &VaultContext) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+    let notes = list_notes(&vault.name)?;
+
+    // Wikilinks name a note by path, by file name or by title.
+    let mut by_path: HashMap<&str, usize> = HashMap::new();
+    let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, note) in notes.iter().enumerate() {
+        by_path.insert(note.relpath.trim_end_matches(".md"), i);
+        if let Some(stem) = Path::new(&note.relpath).file_stem() {
+            by_name
+                .entry(stem.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(i);
+        }
+        by_name
+            .entry(note.title.trim().to_lowercase())
+            .or_default()
+            .push(i);
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (i, note) in notes.iter().enumerate() {
+        let Ok(content) = fs::read_to_string(vault.dir.join(&note.relpath)) else {
+            continue;
+        };
+        let mut targets: HashSet<usize> = HashSet::new();
+        links::map_links(&content, |link| {
+            match link {
+                Link::Wiki(target) => {
+                    let target = target.trim();
+                    let target = target.strip_suffix(".md").unwrap_or(target);
+                    if target.contains('/') {
+                        targets.extend(by_path.get(target));
+                    } else if let Some(found) = by_name.get(&target.to_lowercase()) {
+                        targets.extend(found);
+                    }
+                }
+                Link::Markdown(dest) if !links::is_external(dest) => {
+                    if let Some(resolved) = links::resolve(&note.relpath, dest) {
+                        targets.extend(by_path.get(resolved.trim_end_matches(".md")));
+                    }
+                }
+                Link::Markdown(_) => {}
+            }
+            None
+        });
+        targets.remove(&i);
+        for target in targets {
+            *counts.entry(notes[target].relpath.clone()).or_default() += 1;
+        }
+    }
+    Ok(counts)
+}
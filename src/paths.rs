@@ -0,0 +1,131 @@
+// src/paths.rs
+
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Name of the notemancy subdirectory inside the XDG base directories.
+const APP_DIR: &str = "notemancy";
+
+/// Environment variable read by the core library to locate config.yaml.
+pub const CONF_DIR_ENV: &str = "NOTEMANCY_CONF_DIR";
+
+/// Where the configuration directory came from before it was exported to `CONF_DIR_ENV`.
+static CONFIG_SOURCE: OnceLock<&'static str> = OnceLock::new();
+
+/// A resolved directory and the variable (or fallback) it was derived from.
+pub struct ResolvedDir {
+    pub path: PathBuf,
+    pub source: &'static str,
+}
+
+/// Resolves a directory following the XDG base directory spec:
+/// `override_var` wins, then `$<xdg_var>/notemancy`, then `~/<fallback>/notemancy`.
+fn resolve(
+    override_var: &'static str,
+    xdg_var: &'static str,
+    fallback: &'static str,
+) -> Result<ResolvedDir, Box<dyn Error>> {
+    if let Some(path) = env_path(override_var) {
+        return Ok(ResolvedDir {
+            path,
+            source: override_var,
+        });
+    }
+    if let Some(path) = env_path(xdg_var) {
+        return Ok(ResolvedDir {
+            path: path.join(APP_DIR),
+            source: xdg_var,
+        });
+    }
+    let home = env_path("HOME").ok_or_else(|| {
+        format!(
+            "Cannot determine the {} directory: neither {}, {} nor HOME is set",
+            APP_DIR, override_var, xdg_var
+        )
+    })?;
+    Ok(ResolvedDir {
+        path: home.join(fallback).join(APP_DIR),
+        source: "HOME",
+    })
+}
+
+/// Reads an environment variable as a path. Empty and relative values are
+/// ignored, as the XDG spec requires.
+fn env_path(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// Directory holding config.yaml and default_vault.txt:
+/// `NOTEMANCY_CONF_DIR`, else `$XDG_CONFIG_HOME/notemancy`, else `~/.config/notemancy`.
+pub fn resolve_config_dir() -> Result<ResolvedDir, Box<dyn Error>> {
+    resolve(CONF_DIR_ENV, "XDG_CONFIG_HOME", ".config")
+}
+
+/// Directory holding derived data such as vector stores:
+/// `NOTEMANCY_DATA_DIR`, else `$XDG_DATA_HOME/notemancy`, else `~/.local/share/notemancy`.
+pub fn resolve_data_dir() -> Result<ResolvedDir, Box<dyn Error>> {
+    resolve("NOTEMANCY_DATA_DIR", "XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory holding disposable caches:
+/// `NOTEMANCY_CACHE_DIR`, else `$XDG_CACHE_HOME/notemancy`, else `~/.cache/notemancy`.
+pub fn resolve_cache_dir() -> Result<ResolvedDir, Box<dyn Error>> {
+    resolve("NOTEMANCY_CACHE_DIR", "XDG_CACHE_HOME", ".cache")
+}
+
+/// Returns the configuration directory.
+pub fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(resolve_config_dir()?.path)
+}
+
+/// Returns the data directory.
+pub fn data_dir() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(resolve_data_dir()?.path)
+}
+
+/// Returns the directory vector stores are written to, creating it if needed.
+pub fn store_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let dir = data_dir()?.join("stores");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Points `NOTEMANCY_CONF_DIR` at `flag` (the `--config-dir` option) or the resolved
+/// configuration directory, so that the core library, which only reads that
+/// variable, sees the same location.
+///
+/// Must be called before any other threads are spawned.
+pub fn export_config_dir(flag: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let resolved = match flag {
+        Some(dir) => ResolvedDir {
+            path: std::path::absolute(dir)?,
+            source: "--config-dir",
+        },
+        None => resolve_config_dir()?,
+    };
+    let _ = CONFIG_SOURCE.set(resolved.source);
+    // SAFETY: called from main before any other threads have been spawned.
+    unsafe {
+        env::set_var(CONF_DIR_ENV, &resolved.path);
+    }
+    Ok(())
+}
+
+/// Implements `ncy config path`: prints each resolved directory and its origin.
+pub fn print_paths() -> Result<(), Box<dyn Error>> {
+    let mut config = resolve_config_dir()?;
+    if let Some(source) = CONFIG_SOURCE.get() {
+        config.source = source;
+    }
+    let data = resolve_data_dir()?;
+    let cache = resolve_cache_dir()?;
+    println!("config: {}  ({})", config.path.display(), config.source);
+    println!("data:   {}  ({})", data.path.display(), data.source);
+    println!("cache:  {}  ({})", cache.path.display(), cache.source);
+    println!("stores: {}", data.path.join("stores").display());
+    Ok(())
+}
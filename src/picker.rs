@@ -1,77 +1,313 @@
-use notemancy_core::config::get_vault_dir;
-use notemancy_core::utils::{NoteInfo, list_notes};
-use nucleo_picker::{PickerOptions, nucleo::Config, render::StrRenderer};
-use std::env;
+use crate::config::editor_command;
+use crate::crud::open_in_editor;
+use crate::finder::{FinderOptions, find, find_many};
+use crate::frontmatter;
+use crate::history;
+use crate::mv::{Destination, archive_note, move_note};
+use crate::notes::{VaultNote, list_vault_notes};
+use crate::preview::NotePreview;
+use crate::publish::upload_notes;
+use crate::query::NoteFilter;
+use crate::vault::VaultContext;
+use inquire::{Select, Text};
+use notemancy_core::utils::NoteInfo;
 use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::io::{self, Write};
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use unicode_width::UnicodeWidthStr;
 
-pub fn pick_note() -> Result<(), Box<dyn Error>> {
-    // Determine the default vault from the configuration directory.
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")
-        .map_err(|_| "Environment variable NOTEMANCY_CONF_DIR is not set")?;
-    let default_path = std::path::Path::new(&conf_dir).join("default_vault.txt");
-    let vault = if default_path.exists() {
-        let vault_name = fs::read_to_string(&default_path)?;
-        let trimmed = vault_name.trim().to_string();
-        if trimmed.is_empty() {
-            return Err(
-                "No default vault set; please set one using 'notemancy set <vault_name>'".into(),
-            );
-        } else {
-            trimmed
-        }
-    } else {
-        return Err(
-            "No default vault set; please set one using 'notemancy set <vault_name>'".into(),
-        );
-    };
+/// Opens the picker over the notes of `vaults` and opens the selected note
+/// in the default editor.
+///
+/// Notes marked with tab are handled together: after the picker closes, the
+/// user chooses a bulk action for them (see `BulkAction`).
+pub fn pick_note(vaults: &[VaultContext]) -> Result<(), Box<dyn Error>> {
+    let notes = select_notes(vaults)?;
+    if notes.len() > 1 {
+        return run_bulk_action(vaults, notes);
+    }
+    open_note(vaults, &notes[0])?;
+    Ok(())
+}
 
-    // Retrieve the list of notes from the vault.
-    let notes: Vec<NoteInfo> = list_notes(&vault)?;
-    if notes.is_empty() {
-        return Err(format!("No notes found in vault '{}'", vault).into());
+/// Opens `entry` in the default editor, records the visit and returns the note's path.
+pub fn open_note(vaults: &[VaultContext], entry: &VaultNote) -> Result<PathBuf, Box<dyn Error>> {
+    let vault = &vaults[entry.vault];
+    let full_path = vault.dir.join(&entry.note.relpath);
+    open_in_editor(&full_path)?;
+    history::record_open(vault, &full_path);
+    Ok(full_path)
+}
+
+/// Actions offered for several notes selected in the picker.
+#[derive(Clone, Copy)]
+enum BulkAction {
+    Open,
+    Move,
+    Tag,
+    Archive,
+    Publish,
+    PrintPaths,
+}
+
+impl fmt::Display for BulkAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BulkAction::Open => "Open all in the editor",
+            BulkAction::Move => "Move to project...",
+            BulkAction::Tag => "Add tag...",
+            BulkAction::Archive => "Archive",
+            BulkAction::Publish => "Publish only these",
+            BulkAction::PrintPaths => "Print paths",
+        })
     }
+}
 
-    // Format each note as "title | relative_path".
-    let choices: Vec<String> = notes
+/// Asks what to do with `notes` and does it. Each note is handled in its own vault.
+fn run_bulk_action(vaults: &[VaultContext], notes: Vec<VaultNote>) -> Result<(), Box<dyn Error>> {
+    let actions = vec![
+        BulkAction::Open,
+        BulkAction::Move,
+        BulkAction::Tag,
+        BulkAction::Archive,
+        BulkAction::Publish,
+        BulkAction::PrintPaths,
+    ];
+    let action = Select::new(&format!("{} notes selected:", notes.len()), actions).prompt()?;
+    let paths: Vec<PathBuf> = notes
         .iter()
-        .map(|note| format!("{} | {}", note.title, note.relpath))
+        .map(|entry| vaults[entry.vault].dir.join(&entry.note.relpath))
         .collect();
 
-    // Configure nucleo-picker.
-    let mut picker = PickerOptions::default()
-        .config(Config::DEFAULT.match_paths())
-        .query("")
-        .picker(StrRenderer);
+    match action {
+        BulkAction::Open => {
+            Command::new(editor_command()).args(&paths).status()?;
+            for (entry, path) in notes.iter().zip(&paths) {
+                history::record_open(&vaults[entry.vault], path);
+            }
+        }
+        BulkAction::Move => {
+            let project = Text::new("Move to project:")
+                .with_help_message("A folder inside the vault; leave empty for the vault root")
+                .prompt()?;
+            let destination = Destination::project(&project)?;
+            let rt = tokio::runtime::Runtime::new()?;
+            for_each_note(&notes, "move", |entry| {
+                let vault = &vaults[entry.vault];
+                rt.block_on(move_note(vault, &entry.note.relpath, destination.clone()))
+            });
+        }
+        BulkAction::Tag => {
+            let input = Text::new("Tags to add:")
+                .with_help_message("Separate several tags with spaces or commas")
+                .prompt()?;
+            let tags: Vec<String> = input
+                .split([' ', ','])
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            let mut tagged = 0;
+            for_each_note(&notes, "tag", |entry| {
+                let path = vaults[entry.vault].dir.join(&entry.note.relpath);
+                let content = fs::read_to_string(&path)?;
+                let updated = frontmatter::add_tags(&content, &tags);
+                if updated != content {
+                    fs::write(&path, updated)?;
+                    tagged += 1;
+                }
+                Ok(())
+            });
+            status!("Tagged {} of {} notes", tagged, notes.len());
+        }
+        BulkAction::Archive => {
+            let rt = tokio::runtime::Runtime::new()?;
+            for_each_note(&notes, "archive", |entry| {
+                rt.block_on(archive_note(&vaults[entry.vault], &entry.note))
+            });
+        }
+        // Uploads use a blocking HTTP client, so they must run outside a tokio runtime.
+        BulkAction::Publish => {
+            for (index, vault) in vaults.iter().enumerate() {
+                let selected: Vec<NoteInfo> = notes
+                    .iter()
+                    .filter(|entry| entry.vault == index)
+                    .map(|entry| entry.note.clone())
+                    .collect();
+                if !selected.is_empty() {
+                    upload_notes(vault, selected)?;
+                }
+            }
+        }
+        BulkAction::PrintPaths => {
+            for path in &paths {
+                println!("{}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
 
-    // Push each choice into the picker.
-    let injector = picker.injector();
-    for choice in choices {
-        injector.push(choice);
+/// Runs `action` on each of `notes`, carrying on past the notes it fails for,
+/// which are listed together at the end.
+fn for_each_note(
+    notes: &[VaultNote],
+    verb: &str,
+    mut action: impl FnMut(&VaultNote) -> Result<(), Box<dyn Error>>,
+) {
+    let mut failures = Vec::new();
+    for entry in notes {
+        if let Err(err) = action(entry) {
+            eprintln!("Failed to {} {}: {}", verb, entry.note.relpath, err);
+            failures.push(entry.note.relpath.as_str());
+        }
+    }
+    if !failures.is_empty() {
+        eprintln!(
+            "Failed to {} {} of {} notes: {}",
+            verb,
+            failures.len(),
+            notes.len(),
+            failures.join(", ")
+        );
     }
+}
+
+/// Where notes are chosen: the built-in finder, or an external `fzf` binary.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Builtin,
+    Fzf,
+}
 
-    // Launch the interactive prompt and capture the selection.
-    let selection = picker.pick()?;
-    let selected = match selection {
-        Some(s) => s,
-        None => return Err("No note selected".into()),
+/// Lets the user choose one of the notes of `vault` in the picker, with a preview
+/// of the highlighted note.
+pub fn select_note(vault: &VaultContext) -> Result<NoteInfo, Box<dyn Error>> {
+    let entry = choose_note(std::slice::from_ref(vault), Backend::Builtin)?;
+    Ok(entry.note)
+}
+
+/// Lets the user choose one of the notes of `vaults` with `backend`.
+///
+/// Both backends report the chosen line by its position in the list, so
+/// titles may contain any text.
+pub fn choose_note(vaults: &[VaultContext], backend: Backend) -> Result<VaultNote, Box<dyn Error>> {
+    let (notes, choices) = note_choices(vaults)?;
+    let index = match backend {
+        Backend::Builtin => {
+            let mut preview = NotePreview::new(vaults, &notes);
+            let mut filter = NoteFilter::new(vaults, &notes);
+            let options = FinderOptions {
+                preview: Some(&mut preview),
+                filter: Some(&mut filter),
+                match_paths: true,
+                ..Default::default()
+            };
+            find(&choices, options)?
+        }
+        Backend::Fzf => fzf_select(&choices)?,
     };
+    let index = index.ok_or("No note selected")?;
+    Ok(notes[index].clone())
+}
 
-    // Parse the selected line (expected format: "title | relative_path").
-    let parts: Vec<&str> = selected.split(" | ").collect();
-    if parts.len() < 2 {
-        return Err("Invalid selection format".into());
+/// Runs `fzf` over `choices` and returns the index of the selected one, or
+/// `None` if fzf was closed without a selection.
+///
+/// Each line is fed to fzf as "<index>\t<choice>" with only the choice shown,
+/// and the index is read back from the selected line.
+fn fzf_select(choices: &[String]) -> Result<Option<usize>, Box<dyn Error>> {
+    let input: String = choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| format!("{}\t{}\n", i, choice))
+        .collect();
+
+    // Spawn fzf, feeding it the list of notes via stdin and capturing its stdout.
+    let mut fzf = match Command::new("fzf")
+        .args(["--delimiter", "\t", "--with-nth", "2.."])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(fzf) => fzf,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err("fzf is not installed; drop --fzf to use the built-in picker".into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    {
+        let stdin = fzf.stdin.as_mut().ok_or("Failed to open fzf stdin")?;
+        stdin.write_all(input.as_bytes())?;
     }
-    let rel_path = parts[1].trim();
 
-    // Construct the full file path.
-    let vault_dir = get_vault_dir(&vault)?;
-    let full_path = PathBuf::from(vault_dir).join(rel_path);
+    // fzf exits with 1 when nothing matched and 130 when aborted.
+    let output = fzf.wait_with_output()?;
+    match output.status.code() {
+        Some(0) => {}
+        Some(1 | 130) => return Ok(None),
+        _ => return Err("fzf did not exit successfully".into()),
+    }
+    let selection = String::from_utf8(output.stdout)?;
+    let index = selection
+        .split('\t')
+        .next()
+        .and_then(|id| id.trim().parse::<usize>().ok())
+        .filter(|&i| i < choices.len())
+        .ok_or("Invalid selection from fzf")?;
+    Ok(Some(index))
+}
 
-    // Open the note in the default editor.
-    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-    std::process::Command::new(editor).arg(full_path).status()?;
+/// Lets the user choose notes of `vaults` in the picker and returns every note
+/// marked with tab, or the highlighted note if none were marked.
+pub fn select_notes(vaults: &[VaultContext]) -> Result<Vec<VaultNote>, Box<dyn Error>> {
+    let (notes, choices) = note_choices(vaults)?;
+    let mut preview = NotePreview::new(vaults, &notes);
+    let mut filter = NoteFilter::new(vaults, &notes);
+    let options = FinderOptions {
+        preview: Some(&mut preview),
+        filter: Some(&mut filter),
+        match_paths: true,
+        ..Default::default()
+    };
+    let chosen = find_many(&choices, options)?.ok_or("No note selected")?;
+    Ok(chosen.into_iter().map(|i| notes[i].clone()).collect())
+}
 
-    Ok(())
+/// Lists the notes of `vaults`, most frecent first, with the line shown for
+/// each in the picker: "title | relpath", after a vault column when there is
+/// more than one vault.
+pub fn note_choices(
+    vaults: &[VaultContext],
+) -> Result<(Vec<VaultNote>, Vec<String>), Box<dyn Error>> {
+    let mut notes = list_vault_notes(vaults)?;
+    if notes.is_empty() {
+        let names: Vec<&str> = vaults.iter().map(|v| v.name.as_str()).collect();
+        return Err(format!("No notes found in vault '{}'", names.join("', '")).into());
+    }
+    history::rank(vaults, &mut notes);
+
+    let width = vaults.iter().map(|v| v.name.width() + 1).max().unwrap_or(0);
+    let choices: Vec<String> = notes
+        .iter()
+        .map(|entry| {
+            let line = format!("{} | {}", entry.note.title, entry.note.relpath);
+            if vaults.len() == 1 {
+                return line;
+            }
+            let vault = format!("@{}", vaults[entry.vault].name);
+            let pad = width - vault.width();
+            format!("{}{}  {}", vault, " ".repeat(pad), line)
+        })
+        .collect();
+    Ok((notes, choices))
+}
+
+/// Shows `choices` in the picker and returns the selected one,
+/// or `None` if the picker was closed without a selection.
+pub fn pick_one(choices: Vec<String>) -> Result<Option<String>, Box<dyn Error>> {
+    let index = find(&choices, FinderOptions::default())?;
+    Ok(index.map(|i| choices[i].clone()))
 }
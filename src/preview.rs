@@ -0,0 +1,187 @@
+// src/preview.rs
+
+use notemancy_core::crud::read_note;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+
+use crate::finder::Preview;
+use crate::frontmatter;
+use crate::grep::ContentLine;
+use crate::notes::{VaultNote, backlink_counts};
+use crate::vault::VaultContext;
+
+/// Most body lines kept per note; the pane never shows more than a screenful.
+const MAX_BODY_LINES: usize = 200;
+
+/// Preview pane for the note picker: a frontmatter summary, the backlink count
+/// and the beginning of the note.
+///
+/// Notes are read with `read_note` only when they are first highlighted, and
+/// backlinks are counted on a background thread, so opening the picker stays
+/// fast in large vaults.
+pub struct NotePreview<'a> {
+    vaults: &'a [VaultContext],
+    notes: &'a [VaultNote],
+    cache: HashMap<usize, Vec<String>>,
+    /// Backlink counts for each vault in `vaults`.
+    backlinks: Arc<OnceLock<Vec<HashMap<String, usize>>>>,
+}
+
+impl<'a> NotePreview<'a> {
+    pub fn new(vaults: &'a [VaultContext], notes: &'a [VaultNote]) -> Self {
+        let backlinks = Arc::new(OnceLock::new());
+        let slot = Arc::clone(&backlinks);
+        let owned = vaults.to_vec();
+        thread::spawn(move || {
+            let counts = owned
+                .iter()
+                .map(|vault| backlink_counts(vault).unwrap_or_default())
+                .collect();
+            let _ = slot.set(counts);
+        });
+        NotePreview {
+            vaults,
+            notes,
+            cache: HashMap::new(),
+            backlinks,
+        }
+    }
+
+    /// Reads a note and renders everything but the backlink count.
+    fn render(&self, entry: &VaultNote) -> Vec<String> {
+        let note = &entry.note;
+        let content = match read_note(&self.vaults[entry.vault].name, &note.relpath, true) {
+            Ok(content) => content,
+            Err(e) => return vec![format!("Cannot read {}: {}", note.relpath, e)],
+        };
+        let (fm, body) = frontmatter::split(&content);
+        let fields = fm
+            .and_then(|fm| serde_yaml::from_str::<Value>(fm).ok())
+            .and_then(|value| match value {
+                Value::Mapping(mapping) => Some(mapping),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let location = match self.vaults.len() {
+            1 => note.relpath.clone(),
+            _ => format!("@{}/{}", self.vaults[entry.vault].name, note.relpath),
+        };
+        let mut lines = vec![note.title.clone(), location];
+        for (key, value) in &fields {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            let value = match (key, value) {
+                ("title", _) => continue,
+                ("tags", Value::Sequence(tags)) => tags
+                    .iter()
+                    .filter_map(scalar)
+                    .map(|tag| format!("#{}", tag))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => match scalar(value) {
+                    Some(value) => value,
+                    None => continue,
+                },
+            };
+            lines.push(format!("{}: {}", key, value));
+        }
+        lines.push(String::new());
+        lines.extend(
+            body.trim_start_matches('\n')
+                .lines()
+                .take(MAX_BODY_LINES)
+                .map(str::to_string),
+        );
+        lines
+    }
+}
+
+/// Formats a scalar frontmatter value, or returns `None` for lists and maps.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl Preview for NotePreview<'_> {
+    fn lines(&mut self, index: usize) -> Vec<String> {
+        let Some(entry) = self.notes.get(index) else {
+            return Vec::new();
+        };
+        if !self.cache.contains_key(&index) {
+            let rendered = self.render(entry);
+            self.cache.insert(index, rendered);
+        }
+        let mut lines = self.cache[&index].clone();
+        let backlinks = match self.backlinks.get() {
+            Some(counts) => counts[entry.vault]
+                .get(&entry.note.relpath)
+                .copied()
+                .unwrap_or(0)
+                .to_string(),
+            None => "…".to_string(),
+        };
+        // After the title, path and frontmatter summary.
+        let at = lines
+            .iter()
+            .position(String::is_empty)
+            .unwrap_or(lines.len());
+        lines.insert(at, format!("backlinks: {}", backlinks));
+        lines
+    }
+
+    fn pending(&self) -> bool {
+        self.backlinks.get().is_none()
+    }
+}
+
+/// Lines shown above the matching line in a `LinePreview`.
+const CONTEXT_LINES: usize = 5;
+
+/// Preview pane for `ncy grep`: the lines around the highlighted match, with
+/// line numbers and the match marked.
+pub struct LinePreview<'a> {
+    vault: &'a VaultContext,
+    lines: &'a [ContentLine],
+    /// File contents by relpath, read on first use.
+    files: HashMap<String, Vec<String>>,
+}
+
+impl<'a> LinePreview<'a> {
+    pub fn new(vault: &'a VaultContext, lines: &'a [ContentLine]) -> Self {
+        LinePreview {
+            vault,
+            lines,
+            files: HashMap::new(),
+        }
+    }
+}
+
+impl Preview for LinePreview<'_> {
+    fn lines(&mut self, index: usize) -> Vec<String> {
+        let Some(hit) = self.lines.get(index) else {
+            return Vec::new();
+        };
+        let vault = &self.vault.name;
+        let file = self.files.entry(hit.relpath.clone()).or_insert_with(|| {
+            read_note(vault, &hit.relpath, true)
+                .map(|content| content.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+
+        let start = hit.line.saturating_sub(CONTEXT_LINES + 1);
+        let mut out = vec![hit.relpath.clone(), String::new()];
+        for (i, text) in file.iter().enumerate().skip(start).take(MAX_BODY_LINES) {
+            let marker = if i + 1 == hit.line { '>' } else { ' ' };
+            out.push(format!("{} {:>4}  {}", marker, i + 1, text));
+        }
+        out
+    }
+}
@@ -1,14 +1,14 @@
 // src/publish.rs
 
-use notemancy_core::config::{get_vault_dir, read_config};
+use crate::notes::list_notes;
+use notemancy_core::config::read_config;
 use notemancy_core::crud::read_note;
-use notemancy_core::utils::list_notes;
+use notemancy_core::utils::NoteInfo;
 use reqwest::blocking::Client;
 use serde::Serialize;
-use std::env;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
+
+use crate::vault::VaultContext;
 
 #[derive(Serialize)]
 struct UploadNoteRequest {
@@ -16,20 +16,23 @@ struct UploadNoteRequest {
     content: String,
 }
 
-pub fn publish_notes() -> Result<(), Box<dyn Error>> {
-    // Read the default vault name from default_vault.txt
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")?;
-    let default_vault_path = Path::new(&conf_dir).join("default_vault.txt");
-    let vault = if default_vault_path.exists() {
-        let s = fs::read_to_string(default_vault_path)?;
-        let trimmed = s.trim().to_string();
-        if trimmed.is_empty() {
-            return Err("No default vault set".into());
-        }
-        trimmed
-    } else {
-        return Err("No default vault set".into());
-    };
+/// Uploads every note in `vault` to the configured publish_url.
+pub fn publish_notes(vault: &VaultContext) -> Result<(), Box<dyn Error>> {
+    // List all notes in the vault
+    let notes = list_notes(&vault.name)?;
+    if notes.is_empty() {
+        status!("No notes found in vault '{}'", vault.name);
+        return Ok(());
+    }
+    status!("Found {} notes in vault '{}'", notes.len(), vault.name);
+
+    upload_notes(vault, notes)
+}
+
+/// Uploads `notes` of `vault` to the configured publish_url. Every note is
+/// attempted; if any fails to upload, the error lists them.
+pub fn upload_notes(vault: &VaultContext, notes: Vec<NoteInfo>) -> Result<(), Box<dyn Error>> {
+    let vault = &vault.name;
 
     // Read the publish_url from the configuration (config.yaml)
     let config = read_config()?;
@@ -43,21 +46,14 @@ pub fn publish_notes() -> Result<(), Box<dyn Error>> {
         format!("{}/notes/upload", publish_url)
     };
 
-    // List all notes in the vault
-    let notes = list_notes(&vault)?;
-    if notes.is_empty() {
-        println!("No notes found in vault '{}'", vault);
-        return Ok(());
-    }
-    println!("Found {} notes in vault '{}'", notes.len(), vault);
-
     let client = Client::new();
+    let total = notes.len();
     let mut failures = Vec::new();
 
     // For each note, read its full content and post it to the publish endpoint
     for note in notes {
-        println!("Uploading note: {}", note.relpath);
-        let content = read_note(&vault, &note.relpath, true)?;
+        status!("Uploading note: {}", note.relpath);
+        let content = read_note(vault, &note.relpath, true)?;
         let req_body = UploadNoteRequest {
             relpath: note.relpath.clone(),
             content,
@@ -67,24 +63,28 @@ pub fn publish_notes() -> Result<(), Box<dyn Error>> {
         match res {
             Ok(resp) => {
                 if !resp.status().is_success() {
-                    println!("Failed to upload {}: HTTP {}", note.relpath, resp.status());
+                    eprintln!("Failed to upload {}: HTTP {}", note.relpath, resp.status());
                     failures.push(note.relpath);
                 } else {
-                    println!("Uploaded {} successfully", note.relpath);
+                    status!("Uploaded {} successfully", note.relpath);
                 }
             }
             Err(e) => {
-                println!("Error uploading {}: {}", note.relpath, e);
+                eprintln!("Error uploading {}: {}", note.relpath, e);
                 failures.push(note.relpath);
             }
         }
     }
 
     if !failures.is_empty() {
-        println!("The following notes failed to upload: {:?}", failures);
-    } else {
-        println!("All notes uploaded successfully!");
+        return Err(format!(
+            "{} of {} notes failed to upload: {}",
+            failures.len(),
+            total,
+            failures.join(", ")
+        )
+        .into());
     }
-
+    status!("All notes uploaded successfully!");
     Ok(())
 }
@@ -0,0 +1,461 @@
+// src/query.rs
+
+use chrono::{DateTime, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::finder::Filter;
+use crate::frontmatter;
+use crate::notes::VaultNote;
+use crate::vault::VaultContext;
+
+/// A filter term of the note picker's query.
+enum Term {
+    /// `p:work`: notes in the project folder `work` or one of its subfolders.
+    Project(String),
+    /// `#rust`: notes tagged `rust`, or with a nested tag such as `rust/async`.
+    Tag(String),
+    /// `modified:<7d`: notes last modified within the range.
+    Modified(Range),
+    /// `created:2026-10`: notes created within the range.
+    Created(Range),
+}
+
+/// A span of local time; a missing end leaves that side open.
+struct Range {
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+}
+
+impl Range {
+    fn contains(&self, time: NaiveDateTime) -> bool {
+        self.from.is_none_or(|from| time >= from) && self.to.is_none_or(|to| time < to)
+    }
+}
+
+/// Dates and tags of a note, from its frontmatter or, for dates, the file.
+struct NoteMeta {
+    tags: Vec<String>,
+    created: Option<NaiveDateTime>,
+    modified: Option<NaiveDateTime>,
+}
+
+/// Filter terms for the note picker, typed into the query next to the fuzzy text:
+///
+/// - `p:work` keeps the notes in the project folder `work` and its subfolders,
+/// - `#rust` keeps the notes tagged `rust` (or `rust/...`) in their frontmatter,
+/// - `modified:<7d` and `created:>2w` keep the notes modified or created less
+///   (`<`) or more (`>`) than the given time ago, in `h`ours, `d`ays, `w`eeks,
+///   `m`onths or `y`ears,
+/// - `created:2026-10` keeps the notes created in that year, month or day, and
+///   `created:<2026-10` or `created:>2026-10` those created before or after it.
+///
+/// Dates come from the `created`/`date` and `modified`/`updated` frontmatter
+/// fields, falling back to the file's timestamps. A term that cannot be parsed
+/// yet, such as `modified:<7` while it is being typed, does not narrow the list.
+pub struct NoteFilter<'a> {
+    vaults: &'a [VaultContext],
+    notes: &'a [VaultNote],
+    now: NaiveDateTime,
+    /// The terms `parsed` was built from.
+    words: Vec<String>,
+    parsed: Vec<Term>,
+    /// Metadata by note index, read on first use.
+    meta: HashMap<usize, NoteMeta>,
+}
+
+impl<'a> NoteFilter<'a> {
+    pub fn new(vaults: &'a [VaultContext], notes: &'a [VaultNote]) -> Self {
+        NoteFilter {
+            vaults,
+            notes,
+            now: Local::now().naive_local(),
+            words: Vec::new(),
+            parsed: Vec::new(),
+            meta: HashMap::new(),
+        }
+    }
+}
+
+impl Filter for NoteFilter<'_> {
+    fn is_term(&self, word: &str) -> bool {
+        word.starts_with("p:")
+            || (word.starts_with('#') && word.len() > 1)
+            || word.starts_with("modified:")
+            || word.starts_with("created:")
+    }
+
+    fn keep(&mut self, index: usize, terms: &[&str]) -> bool {
+        if self.words != terms {
+            self.words = terms.iter().map(|t| t.to_string()).collect();
+            self.parsed = terms
+                .iter()
+                .filter_map(|term| parse_term(term, self.now))
+                .collect();
+        }
+        let Some(entry) = self.notes.get(index) else {
+            return false;
+        };
+        let path = self.vaults[entry.vault].dir.join(&entry.note.relpath);
+        self.parsed.iter().all(|term| match term {
+            Term::Project(project) => in_project(&entry.note.relpath, project),
+            Term::Tag(tag) => cached_meta(&mut self.meta, index, &path)
+                .tags
+                .iter()
+                .any(|t| {
+                    t == tag
+                        || t.strip_prefix(tag.as_str())
+                            .is_some_and(|r| r.starts_with('/'))
+                }),
+            Term::Modified(range) => cached_meta(&mut self.meta, index, &path)
+                .modified
+                .is_some_and(|t| range.contains(t)),
+            Term::Created(range) => cached_meta(&mut self.meta, index, &path)
+                .created
+                .is_some_and(|t| range.contains(t)),
+        })
+    }
+}
+
+fn cached_meta<'m>(
+    cache: &'m mut HashMap<usize, NoteMeta>,
+    index: usize,
+    path: &Path,
+) -> &'m NoteMeta {
+    cache.entry(index).or_insert_with(|| read_meta(path))
+}
+
+/// Parses a filter term, or returns `None` if it is incomplete or invalid.
+fn parse_term(word: &str, now: NaiveDateTime) -> Option<Term> {
+    if let Some(project) = word.strip_prefix("p:") {
+        let project = project.trim_matches('/');
+        return (!project.is_empty()).then(|| Term::Project(project.to_lowercase()));
+    }
+    if let Some(tag) = word.strip_prefix('#') {
+        return Some(Term::Tag(tag.to_lowercase()));
+    }
+    if let Some(spec) = word.strip_prefix("modified:") {
+        return parse_range(spec, now).map(Term::Modified);
+    }
+    if let Some(spec) = word.strip_prefix("created:") {
+        return parse_range(spec, now).map(Term::Created);
+    }
+    None
+}
+
+/// Parses `<7d`, `>7d` (also `7d`, meaning `<7d`), `2026-10`, `<2026-10` or `>2026-10`.
+fn parse_range(spec: &str, now: NaiveDateTime) -> Option<Range> {
+    let (op, value) = match spec.strip_prefix('<') {
+        Some(rest) => ('<', rest),
+        None => match spec.strip_prefix('>') {
+            Some(rest) => ('>', rest),
+            None => ('=', spec),
+        },
+    };
+
+    if let Some(since) = ago(value, now) {
+        return Some(match op {
+            '>' => Range {
+                from: None,
+                to: Some(since),
+            },
+            _ => Range {
+                from: Some(since),
+                to: None,
+            },
+        });
+    }
+
+    let (start, end) = period(value)?;
+    Some(match op {
+        '<' => Range {
+            from: None,
+            to: Some(start),
+        },
+        '>' => Range {
+            from: Some(end),
+            to: None,
+        },
+        _ => Range {
+            from: Some(start),
+            to: Some(end),
+        },
+    })
+}
+
+/// Parses an age such as `7d` and returns that long before `now`.
+fn ago(value: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let unit = value.chars().last()?;
+    let count: u32 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'h' => now.checked_sub_signed(TimeDelta::try_hours(count.into())?),
+        'd' => now.checked_sub_days(Days::new(count.into())),
+        'w' => now.checked_sub_days(Days::new(u64::from(count) * 7)),
+        'm' => now.checked_sub_months(Months::new(count)),
+        'y' => now.checked_sub_months(Months::new(count.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// Parses a year (`2026`), month (`2026-10`) or day (`2026-10-17`) and returns
+/// its first moment and the first moment after it.
+fn period(value: &str) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts[0].len() != 4 || parts[1..].iter().any(|p| p.is_empty() || p.len() > 2) {
+        return None;
+    }
+    let year: i32 = parts[0].parse().ok()?;
+    let (start, end) = match parts[..] {
+        [_] => {
+            let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            (start, start.checked_add_months(Months::new(12))?)
+        }
+        [_, month] => {
+            let start = NaiveDate::from_ymd_opt(year, month.parse().ok()?, 1)?;
+            (start, start.checked_add_months(Months::new(1))?)
+        }
+        [_, month, day] => {
+            let start = NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)?;
+            (start, start.succ_opt()?)
+        }
+        _ => return None,
+    };
+    Some((start.and_time(NaiveTime::MIN), end.and_time(NaiveTime::MIN)))
+}
+
+/// Returns true if `relpath` lies in the folder `project` (lowercase) or below it.
+fn in_project(relpath: &str, project: &str) -> bool {
+    let Some(folder) = Path::new(relpath).parent() else {
+        return false;
+    };
+    let folder = folder.to_string_lossy().to_lowercase();
+    folder == project
+        || folder
+            .strip_prefix(project)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn read_meta(path: &Path) -> NoteMeta {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let fields = frontmatter::split(&content)
+        .0
+        .and_then(|fm| serde_yaml::from_str::<Value>(fm).ok());
+    let field_date = |keys: &[&str]| {
+        let fields = fields.as_ref()?;
+        keys.iter()
+            .find_map(|key| fields.get(*key)?.as_str().and_then(parse_datetime))
+    };
+    let file = fs::metadata(path).ok();
+    let file_time = |time: std::io::Result<std::time::SystemTime>| {
+        time.ok()
+            .map(|time| DateTime::<Local>::from(time).naive_local())
+    };
+    NoteMeta {
+        tags: frontmatter::tags(&content)
+            .iter()
+            .map(|tag| tag.trim_start_matches('#').to_lowercase())
+            .collect(),
+        created: field_date(&["created", "date"]).or_else(|| file_time(file.as_ref()?.created())),
+        modified: field_date(&["modified", "updated"])
+            .or_else(|| file_time(file.as_ref()?.modified())),
+    }
+}
+
+/// Parses a frontmatter date: RFC 3339, `YYYY-MM-DD HH:MM[:SS]` or `YYYY-MM-DD`.
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(time);
+        }
+    }
+    let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    Some(date.and_time(NaiveTime::MIN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn now() -> NaiveDateTime {
+        at("2026-10-17 12:00")
+    }
+
+    #[test]
+    fn parse_range_reads_ages_and_periods() {
+        let cases = [
+            ("<7d", Some("2026-10-10 12:00"), None),
+            ("7d", Some("2026-10-10 12:00"), None),
+            (">7d", None, Some("2026-10-10 12:00")),
+            (">2w", None, Some("2026-10-03 12:00")),
+            ("<36h", Some("2026-10-16 00:00"), None),
+            ("<2m", Some("2026-08-17 12:00"), None),
+            (">1y", None, Some("2025-10-17 12:00")),
+            (
+                "2026-10",
+                Some("2026-10-01 00:00"),
+                Some("2026-11-01 00:00"),
+            ),
+            (
+                "2026-10-17",
+                Some("2026-10-17 00:00"),
+                Some("2026-10-18 00:00"),
+            ),
+            ("2026", Some("2026-01-01 00:00"), Some("2027-01-01 00:00")),
+            ("<2026", None, Some("2026-01-01 00:00")),
+            ("<2026-10", None, Some("2026-10-01 00:00")),
+            (">2026-10", Some("2026-11-01 00:00"), None),
+            (
+                "2026-12",
+                Some("2026-12-01 00:00"),
+                Some("2027-01-01 00:00"),
+            ),
+        ];
+        for (spec, from, to) in cases {
+            let range = parse_range(spec, now()).unwrap_or_else(|| panic!("{spec}"));
+            assert_eq!(range.from, from.map(at), "{spec}");
+            assert_eq!(range.to, to.map(at), "{spec}");
+        }
+    }
+
+    #[test]
+    fn parse_range_rejects_incomplete_and_invalid_specs() {
+        let cases = [
+            "",
+            "<",
+            ">",
+            "<7",
+            "7",
+            "d",
+            "<7x",
+            "-7d",
+            "202",
+            "2026-",
+            "2026-1-",
+            "2026-13",
+            "2026-02-30",
+            "2026-100",
+            "26-10",
+            "<2026-10-32",
+        ];
+        for spec in cases {
+            assert!(parse_range(spec, now()).is_none(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn ago_counts_back_from_now() {
+        let cases = [
+            ("0d", Some("2026-10-17 12:00")),
+            ("1h", Some("2026-10-17 11:00")),
+            ("3d", Some("2026-10-14 12:00")),
+            ("1w", Some("2026-10-10 12:00")),
+            ("10m", Some("2025-12-17 12:00")),
+            ("2y", Some("2024-10-17 12:00")),
+            ("7", None),
+            ("7s", None),
+            ("d", None),
+            ("", None),
+            ("2026-10", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(ago(value, now()), expected.map(at), "{value}");
+        }
+    }
+
+    #[test]
+    fn period_covers_a_year_month_or_day() {
+        let cases = [
+            ("2026", Some(("2026-01-01 00:00", "2027-01-01 00:00"))),
+            ("2026-2", Some(("2026-02-01 00:00", "2026-03-01 00:00"))),
+            ("2024-02-29", Some(("2024-02-29 00:00", "2024-03-01 00:00"))),
+            ("2026-02-29", None),
+            ("2026-00", None),
+            ("2026-10-17-1", None),
+            ("20261", None),
+            ("abcd", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                period(value),
+                expected.map(|(start, end)| (at(start), at(end))),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn in_project_matches_the_folder_and_its_subfolders_only() {
+        let cases = [
+            ("work/a.md", "work", true),
+            ("work/sub/a.md", "work", true),
+            ("Work/a.md", "work", true),
+            ("work/sub/a.md", "work/sub", true),
+            ("workshop/a.md", "work", false),
+            ("work.md", "work", false),
+            ("a.md", "work", false),
+            ("home/work/a.md", "work", false),
+            ("work/subway/a.md", "work/sub", false),
+        ];
+        for (relpath, project, expected) in cases {
+            assert_eq!(
+                in_project(relpath, project),
+                expected,
+                "{relpath} in {project}"
+            );
+        }
+    }
+
+    #[test]
+    fn range_includes_its_start_and_excludes_its_end() {
+        let range = parse_range("2026-10", now()).unwrap();
+        assert!(range.contains(at("2026-10-01 00:00")));
+        assert!(range.contains(at("2026-10-31 23:59")));
+        assert!(!range.contains(at("2026-11-01 00:00")));
+        assert!(!range.contains(at("2026-09-30 23:59")));
+    }
+
+    #[test]
+    fn parse_datetime_reads_frontmatter_dates() {
+        let cases = [
+            ("2026-10-17", Some("2026-10-17 00:00")),
+            (" 2026-10-17 ", Some("2026-10-17 00:00")),
+            ("2026-10-17 08:30", Some("2026-10-17 08:30")),
+            ("2026-10-17 08:30:00", Some("2026-10-17 08:30")),
+            ("2026-10-17T08:30:00", Some("2026-10-17 08:30")),
+            ("2026-10-17T08:30:00.5", Some("2026-10-17 00:00")),
+            ("17/10/2026", None),
+            ("2026-10", None),
+            ("today", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_datetime(value), expected.map(at), "{value}");
+        }
+        let utc = DateTime::parse_from_rfc3339("2026-10-17T08:30:00Z").unwrap();
+        assert_eq!(
+            parse_datetime("2026-10-17T08:30:00Z"),
+            Some(utc.with_timezone(&Local).naive_local())
+        );
+    }
+
+    #[test]
+    fn project_terms_are_trimmed_and_lowercased() {
+        let project = |word| match parse_term(word, now()) {
+            Some(Term::Project(project)) => Some(project),
+            _ => None,
+        };
+        assert_eq!(project("p:Work/").as_deref(), Some("work"));
+        assert_eq!(project("p:/work/sub/").as_deref(), Some("work/sub"));
+        assert_eq!(project("p:"), None);
+        assert_eq!(project("p:/"), None);
+    }
+}
@@ -0,0 +1,180 @@
+// src/related.rs
+
+use clap::ValueEnum;
+use notemancy_core::utils::NoteInfo;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs;
+
+use crate::chunk::Fence;
+use crate::config::{EmbeddingConfig, yaml_scalar};
+use crate::frontmatter;
+use crate::search::{Hit, related_notes, show_hits};
+use crate::vault::VaultContext;
+
+/// Heading of the section `ncy related --write section` maintains.
+const SECTION_HEADING: &str = "## Related";
+
+/// Frontmatter field `ncy related --write frontmatter` sets.
+const FIELD: &str = "related";
+
+/// Where `ncy related --write` records the related notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WriteTarget {
+    /// A `## Related` section at the end of the note, replacing an existing one.
+    Section,
+    /// A `related` list in the frontmatter.
+    Frontmatter,
+}
+
+/// Implements `ncy related`: finds the notes of `vault` closest in meaning to
+/// `note`, using the vectors `ncy vectorize` stored for it.
+///
+/// The results are shown as by `ask`, or with `write`, recorded in the note as
+/// wikilinks. `limit` defaults to `embedding.top_k`.
+pub fn related(
+    vault: &VaultContext,
+    note: &NoteInfo,
+    limit: Option<usize>,
+    list: bool,
+    write: Option<WriteTarget>,
+) -> Result<(), Box<dyn Error>> {
+    let limit = limit.unwrap_or_else(|| EmbeddingConfig::load().top_k);
+    let hits = related_notes(vault, note, limit)?;
+    let Some(target) = write else {
+        return show_hits(vault, &hits, list);
+    };
+
+    if hits.is_empty() {
+        status!("No related notes found for {}", note.relpath);
+        return Ok(());
+    }
+    let path = vault.dir.join(&note.relpath);
+    let content = fs::read_to_string(&path)?;
+    let links: Vec<String> = hits.iter().map(wikilink).collect();
+    let updated = match target {
+        WriteTarget::Section => set_section(&content, &links),
+        WriteTarget::Frontmatter => {
+            let list: Vec<String> = links.iter().map(|link| yaml_scalar(link)).collect();
+            frontmatter::set_field(&content, FIELD, &format!("[{}]", list.join(", ")))
+        }
+    };
+    if updated != content {
+        fs::write(&path, updated)?;
+    }
+    status!("Linked {} related notes from {}", hits.len(), note.relpath);
+    Ok(())
+}
+
+/// Links to a hit by its path, which stays unambiguous when titles repeat,
+/// showing its title.
+fn wikilink(hit: &Hit) -> String {
+    let path = hit.note.relpath.trim_end_matches(".md");
+    format!("[[{}|{}]]", path, hit.note.title)
+}
+
+/// Replaces the `## Related` section of `content` (up to the next heading of
+/// the same or a higher level) with a list of `links`, or appends one.
+fn set_section(content: &str, links: &[String]) -> String {
+    let mut section = vec![SECTION_HEADING.to_string(), String::new()];
+    section.extend(links.iter().map(|link| format!("- {}", link)));
+
+    let lines: Vec<&str> = content.lines().collect();
+    let Some((start, end)) = find_section(&lines) else {
+        return format!("{}\n\n{}\n", content.trim_end(), section.join("\n"));
+    };
+
+    let mut out: Vec<String> = lines[..start].iter().map(|l| l.to_string()).collect();
+    out.extend(section);
+    if end < lines.len() {
+        out.push(String::new());
+        out.extend(lines[end..].iter().map(|l| l.to_string()));
+    }
+    format!("{}\n", out.join("\n"))
+}
+
+/// Blanks the lines of the `## Related` section of `content`, so that the links
+/// `--write section` records are not embedded and indexed as part of the note.
+/// The other lines keep their numbers.
+pub fn blank_section(content: &str) -> Cow<'_, str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some((start, end)) = find_section(&lines) else {
+        return Cow::Borrowed(content);
+    };
+    let mut out = String::with_capacity(content.len());
+    for (i, line) in lines.iter().enumerate() {
+        if !(start..end).contains(&i) {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    if !content.ends_with('\n') {
+        out.pop();
+    }
+    Cow::Owned(out)
+}
+
+/// Returns the first line of the `## Related` section of `lines` and the line
+/// after it, skipping code blocks.
+fn find_section(lines: &[&str]) -> Option<(usize, usize)> {
+    let mut fence = Fence::default();
+    let mut start = None;
+    for (i, line) in lines.iter().enumerate() {
+        if fence.fenced(line) {
+            continue;
+        }
+        match start {
+            None if line.trim_end() == SECTION_HEADING => start = Some(i),
+            Some(start) if line.starts_with("# ") || line.starts_with("## ") => {
+                return Some((start, i));
+            }
+            _ => {}
+        }
+    }
+    start.map(|start| (start, lines.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> Vec<String> {
+        vec!["[[b|B]]".to_string()]
+    }
+
+    #[test]
+    fn set_section_appends_or_replaces_the_section() {
+        assert_eq!(
+            set_section("# A\n\ntext\n", &links()),
+            "# A\n\ntext\n\n## Related\n\n- [[b|B]]\n"
+        );
+        assert_eq!(
+            set_section(
+                "text\n\n## Related\n\n- [[old]]\n\n## Next\nmore\n",
+                &links()
+            ),
+            "text\n\n## Related\n\n- [[b|B]]\n\n## Next\nmore\n"
+        );
+    }
+
+    #[test]
+    fn set_section_skips_code_blocks() {
+        let content = "text\n\n```md\n## Related\n```\n";
+        assert_eq!(
+            set_section(content, &links()),
+            "text\n\n```md\n## Related\n```\n\n## Related\n\n- [[b|B]]\n"
+        );
+        let content = "## Related\n\n- [[old]]\n~~~\n# not a heading\n~~~\n";
+        assert_eq!(set_section(content, &links()), "## Related\n\n- [[b|B]]\n");
+    }
+
+    #[test]
+    fn blank_section_keeps_the_other_lines_in_place() {
+        assert_eq!(blank_section("text\n"), "text\n");
+        assert_eq!(
+            blank_section("a\n## Related\n- [[b]]\n## Next\nc"),
+            "a\n\n\n## Next\nc"
+        );
+        assert_eq!(blank_section("a\n\n## Related\n\n- [[b]]\n"), "a\n\n\n\n\n");
+    }
+}
@@ -0,0 +1,399 @@
+// src/schema.rs
+
+use crate::config::{ConfigDocument, read_default_vault};
+use crate::vault::validate_vault_name;
+use chrono::format::{Item, StrftimeItems};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// Top-level keys understood by notemancy.
+const KNOWN_KEYS: &[&str] = &[
+    "vaults",
+    "default_vault",
+    "editor",
+    "publish_url",
+    "embedding",
+    "daily",
+];
+
+/// Keys understood inside the `embedding:` section; all are positive integers.
+const EMBEDDING_KEYS: &[&str] = &["chunk_size", "chunk_overlap", "top_k"];
+
+/// Keys understood inside the `daily:` section.
+const DAILY_KEYS: &[&str] = &[
+    "folder",
+    "format",
+    "week_format",
+    "template",
+    "week_template",
+    "rollover",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found in config.yaml.
+#[derive(Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 1-based line and column, when the problem can be pinned to a location.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((line, column)) = self.position {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", label, self.message)
+    }
+}
+
+/// Collects issues while keeping a handle on the document for locating keys.
+struct Checker<'a> {
+    doc: &'a ConfigDocument,
+    issues: Vec<Issue>,
+}
+
+impl Checker<'_> {
+    fn push(&mut self, severity: Severity, position: Option<(usize, usize)>, message: String) {
+        self.issues.push(Issue {
+            severity,
+            position,
+            message,
+        });
+    }
+
+    fn error_at(&mut self, path: &[&str], message: String) {
+        let position = self.doc.key_position(path);
+        self.push(Severity::Error, position, message);
+    }
+
+    fn vault_error(&mut self, index: usize, field: Option<&str>, message: String) {
+        let position = self.doc.vault_position(index, field);
+        self.push(Severity::Error, position, message);
+    }
+}
+
+/// Checks config.yaml for syntax errors, unknown keys, wrong types and missing
+/// vault directories, returning every problem found.
+///
+/// # Errors
+///
+/// Returns an error only if the configuration directory or file cannot be read.
+pub fn validate_config() -> Result<Vec<Issue>, Box<dyn Error>> {
+    let doc = ConfigDocument::load()?;
+    if !doc.path().exists() {
+        return Err(format!("Config file not found at {}", doc.path().display()).into());
+    }
+
+    let mut checker = Checker {
+        doc: &doc,
+        issues: Vec::new(),
+    };
+
+    let value: Value = match serde_yaml::from_str(&doc.text()) {
+        Ok(value) => value,
+        Err(e) => {
+            let position = e.location().map(|l| (l.line(), l.column()));
+            checker.push(Severity::Error, position, e.to_string());
+            return Ok(checker.issues);
+        }
+    };
+
+    let root = match value {
+        Value::Mapping(root) => root,
+        Value::Null => {
+            checker.push(
+                Severity::Error,
+                None,
+                "config.yaml is empty; define at least one vault under 'vaults:'".to_string(),
+            );
+            return Ok(checker.issues);
+        }
+        _ => {
+            checker.push(
+                Severity::Error,
+                Some((1, 1)),
+                "config.yaml must be a mapping of keys to values".to_string(),
+            );
+            return Ok(checker.issues);
+        }
+    };
+
+    for key in root.keys() {
+        match key.as_str() {
+            Some(k) if KNOWN_KEYS.contains(&k) => {}
+            Some(k) => {
+                let position = doc.key_position(&[k]);
+                checker.push(Severity::Warning, position, format!("unknown key '{}'", k));
+            }
+            None => checker.push(
+                Severity::Warning,
+                None,
+                format!("ignoring non-string key {:?}", key),
+            ),
+        }
+    }
+
+    let names = check_vaults(&mut checker, root.get("vaults"));
+
+    match root.get("default_vault") {
+        None => {}
+        Some(Value::String(name)) if !names.contains(name) => checker.error_at(
+            &["default_vault"],
+            format!(
+                "default_vault '{}' is not one of the configured vaults",
+                name
+            ),
+        ),
+        Some(Value::String(_)) => {}
+        Some(_) => checker.error_at(
+            &["default_vault"],
+            "default_vault must be a vault name".to_string(),
+        ),
+    }
+
+    match root.get("editor") {
+        None | Some(Value::String(_)) => {}
+        Some(_) => checker.error_at(&["editor"], "editor must be a command name".to_string()),
+    }
+
+    match root.get("publish_url") {
+        None => {}
+        Some(Value::String(url)) if url.starts_with("http://") || url.starts_with("https://") => {}
+        Some(Value::String(url)) => checker.error_at(
+            &["publish_url"],
+            format!("publish_url '{}' must start with http:// or https://", url),
+        ),
+        Some(_) => checker.error_at(&["publish_url"], "publish_url must be a URL".to_string()),
+    }
+
+    match root.get("embedding") {
+        None => {}
+        Some(Value::Mapping(embedding)) => check_embedding(&mut checker, embedding),
+        Some(_) => checker.error_at(
+            &["embedding"],
+            "embedding must be a mapping of settings".to_string(),
+        ),
+    }
+
+    match root.get("daily") {
+        None => {}
+        Some(Value::Mapping(daily)) => check_daily(&mut checker, daily),
+        Some(_) => checker.error_at(
+            &["daily"],
+            "daily must be a mapping of settings".to_string(),
+        ),
+    }
+
+    if let Ok(Some(default)) = read_default_vault()
+        && !names.contains(&default)
+    {
+        checker.push(
+            Severity::Warning,
+            None,
+            format!(
+                "default vault '{}' (set with 'ncy set') is not one of the configured vaults",
+                default
+            ),
+        );
+    }
+
+    Ok(checker.issues)
+}
+
+/// Checks the `vaults:` list and returns the names of the well-formed vaults.
+fn check_vaults(checker: &mut Checker, vaults: Option<&Value>) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let vaults = match vaults {
+        Some(Value::Sequence(vaults)) if !vaults.is_empty() => vaults,
+        Some(Value::Sequence(_)) | None => {
+            checker.error_at(
+                &["vaults"],
+                "no vaults defined; add one with 'ncy vault add <name> <path>'".to_string(),
+            );
+            return names;
+        }
+        Some(_) => {
+            checker.error_at(
+                &["vaults"],
+                "vaults must be a list of entries with 'name' and 'directory'".to_string(),
+            );
+            return names;
+        }
+    };
+
+    for (i, vault) in vaults.iter().enumerate() {
+        let Value::Mapping(vault) = vault else {
+            checker.vault_error(
+                i,
+                None,
+                format!("vault #{} must have 'name' and 'directory' keys", i + 1),
+            );
+            continue;
+        };
+
+        let name = match vault.get("name") {
+            Some(Value::String(name)) => {
+                if let Err(e) = validate_vault_name(name) {
+                    let position = checker.doc.vault_position(i, Some("name"));
+                    checker.push(Severity::Warning, position, e.to_string());
+                }
+                if !names.insert(name.clone()) {
+                    checker.vault_error(i, Some("name"), format!("duplicate vault '{}'", name));
+                }
+                name.clone()
+            }
+            Some(_) => {
+                checker.vault_error(i, Some("name"), "vault name must be a string".to_string());
+                format!("#{}", i + 1)
+            }
+            None => {
+                checker.vault_error(i, None, format!("vault #{} has no 'name'", i + 1));
+                format!("#{}", i + 1)
+            }
+        };
+
+        match vault.get("directory") {
+            Some(Value::String(dir)) => {
+                let path = Path::new(dir);
+                if !path.is_absolute() {
+                    checker.vault_error(
+                        i,
+                        Some("directory"),
+                        format!("vault '{}': directory '{}' must be absolute", name, dir),
+                    );
+                } else if !path.is_dir() {
+                    checker.vault_error(
+                        i,
+                        Some("directory"),
+                        format!("vault '{}': directory '{}' does not exist", name, dir),
+                    );
+                }
+            }
+            Some(_) => checker.vault_error(
+                i,
+                Some("directory"),
+                format!("vault '{}': directory must be a path", name),
+            ),
+            None => checker.vault_error(i, None, format!("vault '{}' has no 'directory'", name)),
+        }
+    }
+    names
+}
+
+fn check_embedding(checker: &mut Checker, embedding: &Mapping) {
+    for (key, value) in embedding {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if !EMBEDDING_KEYS.contains(&key) {
+            let position = checker.doc.key_position(&["embedding", key]);
+            checker.push(
+                Severity::Warning,
+                position,
+                format!("unknown embedding setting '{}'", key),
+            );
+        } else if !value
+            .as_u64()
+            .is_some_and(|n| n > 0 || key == "chunk_overlap")
+        {
+            checker.error_at(
+                &["embedding", key],
+                format!("embedding.{} must be a positive integer", key),
+            );
+        }
+    }
+
+    let size = embedding.get("chunk_size").and_then(Value::as_u64);
+    let overlap = embedding.get("chunk_overlap").and_then(Value::as_u64);
+    if let (Some(size), Some(overlap)) = (size, overlap)
+        && overlap >= size
+    {
+        checker.error_at(
+            &["embedding", "chunk_overlap"],
+            "embedding.chunk_overlap must be smaller than embedding.chunk_size".to_string(),
+        );
+    }
+}
+
+fn check_daily(checker: &mut Checker, daily: &Mapping) {
+    for (key, value) in daily {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let path = ["daily", key];
+        match (key, value) {
+            ("rollover", Value::Bool(_)) => {}
+            ("rollover", _) => {
+                checker.error_at(&path, "daily.rollover must be true or false".to_string())
+            }
+            ("folder", Value::String(folder)) if Path::new(folder).is_absolute() => checker
+                .error_at(
+                    &path,
+                    format!("daily.folder '{}' must be relative to the vault", folder),
+                ),
+            ("format" | "week_format", Value::String(format))
+                if StrftimeItems::new(format).any(|item| item == Item::Error) =>
+            {
+                checker.error_at(
+                    &path,
+                    format!("daily.{} '{}' is not a valid date format", key, format),
+                )
+            }
+            (_, Value::String(_)) if DAILY_KEYS.contains(&key) => {}
+            _ if DAILY_KEYS.contains(&key) => {
+                checker.error_at(&path, format!("daily.{} must be a string", key))
+            }
+            _ => {
+                let position = checker.doc.key_position(&path);
+                checker.push(
+                    Severity::Warning,
+                    position,
+                    format!("unknown daily setting '{}'", key),
+                );
+            }
+        }
+    }
+}
+
+/// Runs `validate_config` and prints every issue, prefixed with the config path.
+/// Returns the number of errors (warnings are printed but not counted).
+pub fn report_issues() -> Result<usize, Box<dyn Error>> {
+    let issues = validate_config()?;
+    let path = ConfigDocument::load()?.path().display().to_string();
+    for issue in &issues {
+        match issue.position {
+            Some(_) => eprintln!("{}:{}", path, issue),
+            None => eprintln!("{}: {}", path, issue),
+        }
+    }
+    Ok(issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count())
+}
+
+/// Implements `ncy config check`.
+pub fn check_config() -> Result<(), Box<dyn Error>> {
+    match report_issues()? {
+        0 => {
+            status!("config.yaml is valid");
+            Ok(())
+        }
+        1 => Err("1 problem found in config.yaml".into()),
+        n => Err(format!("{} problems found in config.yaml", n).into()),
+    }
+}
@@ -0,0 +1,399 @@
+// src/search.rs
+
+use clap::ValueEnum;
+use hddb::core::{SearchResult, Store};
+use notemancy_core::ai::sentence_transformer::generate_embedding;
+use notemancy_core::utils::NoteInfo;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use tch::Tensor;
+
+use crate::bm25::Bm25Index;
+use crate::chunk::{ChunkMeta, note_of};
+use crate::config::EmbeddingConfig;
+use crate::crud::open_in_editor_at;
+use crate::finder::{FinderOptions, find};
+use crate::grep::ContentLine;
+use crate::history;
+use crate::notes::list_notes;
+use crate::preview::LinePreview;
+use crate::store::{Manifest, load_vault_store, note_ids};
+use crate::vault::VaultContext;
+
+/// Constant of reciprocal rank fusion; larger values flatten the difference
+/// between the top ranks.
+const RRF_K: f64 = 60.0;
+
+/// Number of notes taken from each ranking before fusing them.
+const FUSION_DEPTH: usize = 50;
+
+/// How `ncy ask` ranks notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SearchMode {
+    /// By the words of the query, with BM25; finds exact identifiers.
+    Lexical,
+    /// By meaning, with the embeddings of the vector store.
+    Semantic,
+    /// Both, fused with reciprocal rank fusion.
+    Hybrid,
+}
+
+/// A note found by search.
+pub struct Hit {
+    pub note: NoteInfo,
+    /// Relevance of the note: the cosine similarity (at most 1) or BM25 score
+    /// of its best matching chunk, or the fused score of a hybrid search.
+    pub score: f64,
+    /// Where the best matching chunk lies in the note; `None` for stores built
+    /// before notes were chunked.
+    pub chunk: Option<ChunkMeta>,
+}
+
+impl Hit {
+    /// Line the best matching chunk starts at, or 1.
+    pub fn line(&self) -> usize {
+        self.chunk.as_ref().map_or(1, |chunk| chunk.start)
+    }
+
+    /// Headings enclosing the best matching chunk, or "".
+    pub fn heading(&self) -> &str {
+        self.chunk
+            .as_ref()
+            .map_or("", |chunk| chunk.heading.as_str())
+    }
+}
+
+/// Loads the vector store of `vault`, failing if it was never vectorized.
+fn open_store(vault: &VaultContext) -> Result<Store, Box<dyn Error>> {
+    load_vault_store(&vault.name)?.ok_or_else(|| {
+        format!(
+            "Vault '{}' has not been vectorized yet; run 'ncy vectorize' first",
+            vault.name
+        )
+        .into()
+    })
+}
+
+/// Scores every vector of `store` against each of `queries` and returns the
+/// `limit` notes of `vault` with the best matching chunk, best first. Notes that
+/// were deleted since the vault was vectorized, and `exclude`, are skipped.
+fn nearest_notes(
+    vault: &VaultContext,
+    store: &Store,
+    queries: &[Tensor],
+    exclude: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let mut scores = Vec::new();
+    for query in queries {
+        match store
+            .cosine_search(query, None)
+            .map_err(|e| format!("Failed to search vector store: {}", e))?
+        {
+            SearchResult::WithScores(found) => {
+                scores.extend(found.into_iter().map(|(_, id, score)| (id, score)))
+            }
+            SearchResult::WithoutScores(_) => {}
+        }
+    }
+    best_per_note(vault, &scores, exclude, limit)
+}
+
+/// Turns chunk scores into the `limit` best notes of `vault`, each scoring as
+/// its best chunk. Notes that were deleted since the vault was vectorized, and
+/// `exclude`, are skipped.
+fn best_per_note(
+    vault: &VaultContext,
+    scores: &[(String, f64)],
+    exclude: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let mut best: HashMap<&str, (&str, f64)> = HashMap::new();
+    for (id, score) in scores {
+        let relpath = note_of(id);
+        if exclude == Some(relpath) {
+            continue;
+        }
+        let entry = best.entry(relpath).or_insert((id, *score));
+        if *score > entry.1 {
+            *entry = (id, *score);
+        }
+    }
+
+    let manifest = Manifest::load(&vault.name)?;
+    let mut notes: HashMap<String, NoteInfo> = list_notes(&vault.name)?
+        .into_iter()
+        .map(|note| (note.relpath.clone(), note))
+        .collect();
+    let mut hits: Vec<Hit> = best
+        .into_iter()
+        .filter_map(|(relpath, (id, score))| {
+            let note = notes.remove(relpath)?;
+            Some(Hit {
+                note,
+                score,
+                chunk: manifest.chunk(id).cloned(),
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Embeds `query` and returns the `limit` notes of `vault` most similar to it,
+/// best first. A note scores as its best matching chunk. Notes that were
+/// deleted since the vault was vectorized are skipped.
+pub fn semantic_search(
+    vault: &VaultContext,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let store = open_store(vault)?;
+    let embeddings = generate_embedding(query)?;
+    let embedding = embeddings
+        .first()
+        .ok_or("No embedding generated for the query")?;
+    let tensor =
+        Tensor::f_from_slice(embedding).map_err(|e| format!("Failed to create tensor: {}", e))?;
+    nearest_notes(vault, &store, &[tensor], None, limit)
+}
+
+/// Returns the `limit` notes of `vault` whose text best matches the words of
+/// `query`, best first, ranked with BM25 over the chunk index built by
+/// `ncy vectorize`. A note scores as its best matching chunk.
+pub fn lexical_search(
+    vault: &VaultContext,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let index = Bm25Index::load(&vault.name)?.ok_or_else(|| {
+        format!(
+            "Vault '{}' has not been indexed yet; run 'ncy vectorize' first",
+            vault.name
+        )
+    })?;
+    best_per_note(vault, &index.search(query), None, limit)
+}
+
+/// Fuses the semantic and lexical rankings of `query` with reciprocal rank
+/// fusion: a note scores the sum of `1 / (RRF_K + rank)` over the rankings it
+/// appears in, so a note found by both beats one found by either alone. Each
+/// note jumps to the chunk of the ranking that placed it higher. Vaults whose
+/// word index was never built are searched by meaning only.
+pub fn hybrid_search(
+    vault: &VaultContext,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let Some(index) = Bm25Index::load(&vault.name)? else {
+        eprintln!(
+            "Warning: Vault '{}' has no word index yet; searching by meaning only. Run 'ncy vectorize' to build it.",
+            vault.name
+        );
+        return semantic_search(vault, query, limit);
+    };
+    let depth = FUSION_DEPTH.max(limit);
+    let rankings = vec![
+        semantic_search(vault, query, depth)?,
+        best_per_note(vault, &index.search(query), None, depth)?,
+    ];
+    Ok(fuse(rankings, limit))
+}
+
+/// Fuses `rankings` of hits, best first each, with reciprocal rank fusion and
+/// keeps the best `limit` notes.
+fn fuse(rankings: Vec<Vec<Hit>>, limit: usize) -> Vec<Hit> {
+    // Fused score, best rank and the hit at that rank, by note.
+    let mut fused: HashMap<String, (f64, usize, Hit)> = HashMap::new();
+    for ranking in rankings {
+        for (rank, hit) in ranking.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            match fused.get_mut(&hit.note.relpath) {
+                Some(entry) => {
+                    entry.0 += score;
+                    if rank < entry.1 {
+                        entry.1 = rank;
+                        entry.2 = hit;
+                    }
+                }
+                None => {
+                    fused.insert(hit.note.relpath.clone(), (score, rank, hit));
+                }
+            }
+        }
+    }
+
+    let mut hits: Vec<Hit> = fused
+        .into_values()
+        .map(|(score, _, hit)| Hit { score, ..hit })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.note.relpath.cmp(&b.note.relpath))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// Returns the `limit` notes of `vault` closest in meaning to `note`, best
+/// first, using the vectors already stored for the note's chunks: another note
+/// scores as its best match with any of them.
+pub fn related_notes(
+    vault: &VaultContext,
+    note: &NoteInfo,
+    limit: usize,
+) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let store = open_store(vault)?;
+    let vectors: Vec<Tensor> = note_ids(&store, &note.relpath)
+        .iter()
+        .filter_map(|id| store.get_vector(Some(id), None).ok())
+        .collect();
+    if vectors.is_empty() {
+        return Err(format!(
+            "{} has not been vectorized yet; run 'ncy vectorize' first",
+            note.relpath
+        )
+        .into());
+    }
+    nearest_notes(vault, &store, &vectors, Some(&note.relpath), limit)
+}
+
+/// Implements `ncy ask`: finds the notes of `vault` that best match `query`,
+/// by meaning, by the words it contains, or (the default) both.
+///
+/// The results are shown as by `show_hits`. `limit` defaults to `embedding.top_k`.
+pub fn ask(
+    vault: &VaultContext,
+    query: &str,
+    limit: Option<usize>,
+    list: bool,
+    mode: SearchMode,
+) -> Result<(), Box<dyn Error>> {
+    if query.trim().is_empty() {
+        return Err("The query must not be empty".into());
+    }
+    let limit = limit.unwrap_or_else(|| EmbeddingConfig::load().top_k);
+    let hits = match mode {
+        SearchMode::Lexical => lexical_search(vault, query, limit)?,
+        SearchMode::Semantic => semantic_search(vault, query, limit)?,
+        SearchMode::Hybrid => hybrid_search(vault, query, limit)?,
+    };
+    show_hits(vault, &hits, list)
+}
+
+/// Shows search results in the picker, best first, and opens the chosen note in
+/// the editor at the matching section. When stdout is not a terminal, or with
+/// `list`, they are printed as `score  relpath:line  heading` instead.
+pub fn show_hits(vault: &VaultContext, hits: &[Hit], list: bool) -> Result<(), Box<dyn Error>> {
+    if list || !io::stdout().is_terminal() {
+        for hit in hits {
+            let line = format!(
+                "{:.3}  {}:{}  {}",
+                hit.score,
+                hit.note.relpath,
+                hit.line(),
+                hit.heading()
+            );
+            println!("{}", line.trim_end());
+        }
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        return Err(format!("No vectorized notes found in vault '{}'", vault.name).into());
+    }
+    let items: Vec<String> = hits
+        .iter()
+        .map(|hit| {
+            let item = format!(
+                "{:.3}  {} | {}  {}",
+                hit.score,
+                hit.note.title,
+                hit.note.relpath,
+                hit.heading()
+            );
+            item.trim_end().to_string()
+        })
+        .collect();
+    let lines: Vec<ContentLine> = hits
+        .iter()
+        .map(|hit| ContentLine {
+            relpath: hit.note.relpath.clone(),
+            line: hit.line(),
+            text: hit.heading().to_string(),
+        })
+        .collect();
+    let mut preview = LinePreview::new(vault, &lines);
+    let options = FinderOptions {
+        preview: Some(&mut preview),
+        ..Default::default()
+    };
+    let Some(index) = find(&items, options)? else {
+        return Err("No note selected".into());
+    };
+    let chosen = &lines[index];
+    let path = vault.dir.join(&chosen.relpath);
+    open_in_editor_at(&path, chosen.line)?;
+    history::record_open(vault, &path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(relpath: &str, start: usize) -> Hit {
+        Hit {
+            note: NoteInfo {
+                relpath: relpath.to_string(),
+                title: relpath.to_string(),
+            },
+            score: 0.0,
+            chunk: Some(ChunkMeta {
+                heading: String::new(),
+                start,
+                end: start,
+            }),
+        }
+    }
+
+    fn relpaths(hits: &[Hit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.note.relpath.as_str()).collect()
+    }
+
+    #[test]
+    fn fuse_ranks_notes_found_by_both_rankings_first() {
+        let semantic = vec![hit("a.md", 1), hit("b.md", 1), hit("c.md", 1)];
+        let lexical = vec![hit("d.md", 1), hit("c.md", 1)];
+        let hits = fuse(vec![semantic, lexical], 10);
+        assert_eq!(relpaths(&hits), ["c.md", "a.md", "d.md", "b.md"]);
+        let expected = 1.0 / (RRF_K + 3.0) + 1.0 / (RRF_K + 2.0);
+        assert!((hits[0].score - expected).abs() < 1e-12);
+        assert!((hits[1].score - 1.0 / (RRF_K + 1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fuse_keeps_the_chunk_of_the_better_rank() {
+        let semantic = vec![hit("a.md", 1), hit("b.md", 10)];
+        let lexical = vec![hit("b.md", 20), hit("a.md", 30)];
+        let hits = fuse(vec![semantic, lexical], 10);
+        let line = |relpath: &str| {
+            hits.iter()
+                .find(|h| h.note.relpath == relpath)
+                .unwrap()
+                .line()
+        };
+        assert_eq!(line("a.md"), 1);
+        assert_eq!(line("b.md"), 20);
+    }
+
+    #[test]
+    fn fuse_truncates_to_limit() {
+        let ranking = vec![hit("a.md", 1), hit("b.md", 1), hit("c.md", 1)];
+        assert_eq!(relpaths(&fuse(vec![ranking], 2)), ["a.md", "b.md"]);
+        assert!(fuse(vec![Vec::new(), Vec::new()], 5).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+// src/setup.rs
+
+use crate::config::{set_default_vault, yaml_scalar};
+use crate::vault::validate_vault_name;
+use inquire::validator::Validation;
+use inquire::{Confirm, Select, Text};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Answers collected by the setup wizard.
+struct SetupAnswers {
+    vaults: Vec<(String, PathBuf)>,
+    default_vault: String,
+    editor: String,
+    publish_url: Option<String>,
+}
+
+/// Runs the interactive first-run setup and writes `config_file`.
+///
+/// The wizard asks for one or more vaults, the default vault, the editor and an
+/// optional publish URL, then writes a commented config.yaml and records the
+/// default vault with `set_default_vault`.
+pub fn run_setup_wizard(config_file: &Path) -> Result<(), Box<dyn Error>> {
+    println!("Welcome to notemancy! Let's set up your first vault.");
+    println!("(Run 'ncy init --edit' to write config.yaml by hand instead.)");
+
+    let answers = ask()?;
+    fs::write(config_file, render_config(&answers))?;
+    set_default_vault(&answers.default_vault)?;
+
+    status!("Wrote {}", config_file.display());
+    status!("Default vault set to {}", answers.default_vault);
+    Ok(())
+}
+
+fn ask() -> Result<SetupAnswers, Box<dyn Error>> {
+    let mut vaults: Vec<(String, PathBuf)> = Vec::new();
+    loop {
+        let taken: Vec<String> = vaults.iter().map(|(name, _)| name.clone()).collect();
+        let default_name = if vaults.is_empty() { "main" } else { "" };
+        let name = Text::new("Vault name:")
+            .with_default(default_name)
+            .with_help_message("A short name used as '@name' on the command line")
+            .with_validator(move |name: &str| {
+                if let Err(e) = validate_vault_name(name) {
+                    return Ok(Validation::Invalid(e.to_string().into()));
+                }
+                if taken.iter().any(|t| t == name) {
+                    return Ok(Validation::Invalid("That vault already exists".into()));
+                }
+                Ok(Validation::Valid)
+            })
+            .prompt()?;
+
+        let directory = ask_directory(&name)?;
+        vaults.push((name, directory));
+
+        if !Confirm::new("Add another vault?")
+            .with_default(false)
+            .prompt()?
+        {
+            break;
+        }
+    }
+
+    let names: Vec<String> = vaults.iter().map(|(name, _)| name.clone()).collect();
+    let default_vault = if names.len() == 1 {
+        names[0].clone()
+    } else {
+        Select::new("Default vault:", names).prompt()?
+    };
+
+    let env_editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let editor = Text::new("Editor for notes:")
+        .with_default(&env_editor)
+        .prompt()?;
+
+    let publish_url = Text::new("Publish URL (optional):")
+        .with_help_message("Server that 'ncy publish' uploads notes to; leave empty to skip")
+        .with_validator(|url: &str| {
+            if url.is_empty() || url.starts_with("http://") || url.starts_with("https://") {
+                Ok(Validation::Valid)
+            } else {
+                Ok(Validation::Invalid(
+                    "The URL must start with http:// or https://".into(),
+                ))
+            }
+        })
+        .prompt()?;
+
+    Ok(SetupAnswers {
+        vaults,
+        default_vault,
+        editor,
+        publish_url: (!publish_url.is_empty()).then_some(publish_url),
+    })
+}
+
+/// Asks for the directory of vault `name`, offering to create it if it does not exist.
+fn ask_directory(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    loop {
+        let input = Text::new(&format!("Directory for '{}':", name))
+            .with_validator(|dir: &str| {
+                if dir.trim().is_empty() {
+                    Ok(Validation::Invalid("Please enter a directory".into()))
+                } else {
+                    Ok(Validation::Valid)
+                }
+            })
+            .prompt()?;
+        let path = expand_home(input.trim());
+
+        if !path.exists() {
+            let create = Confirm::new(&format!("{} does not exist. Create it?", path.display()))
+                .with_default(true)
+                .prompt()?;
+            if !create {
+                continue;
+            }
+            fs::create_dir_all(&path)?;
+        } else if !path.is_dir() {
+            println!("{} is not a directory", path.display());
+            continue;
+        }
+        return Ok(path.canonicalize()?);
+    }
+}
+
+/// Expands a leading `~` to the home directory.
+fn expand_home(input: &str) -> PathBuf {
+    if let Some(rest) = input.strip_prefix('~')
+        && (rest.is_empty() || rest.starts_with('/'))
+        && let Ok(home) = env::var("HOME")
+    {
+        return PathBuf::from(format!("{}{}", home, rest));
+    }
+    PathBuf::from(input)
+}
+
+fn render_config(answers: &SetupAnswers) -> String {
+    let mut out = String::new();
+    out.push_str("# notemancy configuration\n");
+    out.push_str("# Check this file with 'ncy config check'.\n\n");
+
+    out.push_str("# Directories of markdown notes. Manage them with 'ncy vault'.\n");
+    out.push_str("vaults:\n");
+    for (name, dir) in &answers.vaults {
+        out.push_str(&format!("  - name: {}\n", yaml_scalar(name)));
+        out.push_str(&format!(
+            "    directory: {}\n",
+            yaml_scalar(&dir.to_string_lossy())
+        ));
+    }
+
+    out.push_str("\n# Editor used to open notes; overrides $EDITOR.\n");
+    out.push_str(&format!("editor: {}\n", yaml_scalar(&answers.editor)));
+
+    out.push_str("\n# Server that 'ncy publish' uploads notes to.\n");
+    match &answers.publish_url {
+        Some(url) => out.push_str(&format!("publish_url: {}\n", yaml_scalar(url))),
+        None => out.push_str("# publish_url: https://notes.example.com\n"),
+    }
+    out
+}
@@ -0,0 +1,166 @@
+// src/store.rs
+
+use hddb::core::{Store, dump_store, load_store};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::chunk::{ChunkMeta, note_of, parse_chunk_id, rebase_id};
+use crate::paths::{config_dir, store_dir};
+
+/// What the vector store of a vault was built from, kept next to the store so
+/// that unchanged notes are not embedded again.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    /// Chunking settings the store was built with; after a change every note is
+    /// embedded again. Zero in manifests written before notes were chunked.
+    #[serde(default)]
+    pub chunk_size: usize,
+    #[serde(default)]
+    pub chunk_overlap: usize,
+    /// Embedded notes by path relative to the vault.
+    pub notes: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    /// FNV-1a hash of the note body, in hex.
+    pub hash: String,
+    /// Modification time of the note file, in nanoseconds since the Unix epoch.
+    pub mtime: u64,
+    /// The note's chunks; chunk `i` is stored under `chunk_id(relpath, i)`.
+    #[serde(default)]
+    pub chunks: Vec<ChunkMeta>,
+}
+
+impl Manifest {
+    /// Loads the manifest of `vault_name`, which is empty if there is none.
+    pub fn load(vault_name: &str) -> Result<Self, Box<dyn Error>> {
+        let path = manifest_path(vault_name)?;
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, vault_name: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(manifest_path(vault_name)?, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Returns where the chunk stored under `id` lies in its note.
+    pub fn chunk(&self, id: &str) -> Option<&ChunkMeta> {
+        let (relpath, index) = parse_chunk_id(id)?;
+        self.notes.get(relpath)?.chunks.get(index)
+    }
+}
+
+/// Name of the hddb store holding the note embeddings of `vault_name`.
+pub fn store_name(vault_name: &str) -> String {
+    format!("{}_vectors", vault_name)
+}
+
+/// Path of the file `dump_store` writes the store of `vault_name` to.
+pub fn store_path(vault_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(store_dir()?.join(format!("{}.bin", store_name(vault_name))))
+}
+
+/// Path of the manifest recording which note contents the store of `vault_name`
+/// was built from (see `vectorize::vectorize_vault`).
+pub fn manifest_path(vault_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(store_dir()?.join(format!("{}.manifest.yaml", store_name(vault_name))))
+}
+
+/// Path of the BM25 index built over the notes of `vault_name` along with its
+/// vector store (see `bm25::Bm25Index`).
+pub fn index_path(vault_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(store_dir()?.join(format!("{}.bm25.bin", store_name(vault_name))))
+}
+
+/// Loads the vector store of `vault_name`, or returns `None` if the vault has
+/// not been vectorized yet.
+pub fn load_vault_store(vault_name: &str) -> Result<Option<Store>, Box<dyn Error>> {
+    migrate_legacy_store(vault_name)?;
+    if !store_path(vault_name)?.exists() {
+        return Ok(None);
+    }
+    let dir = store_dir()?;
+    let store = load_store(&dir.to_string_lossy(), &store_name(vault_name))
+        .map_err(|e| format!("Failed to load vector store: {}", e))?;
+    Ok(Some(store))
+}
+
+/// Moves the store of `vault_name` from the configuration directory, where
+/// stores were written before they moved to the data directory, to `store_path`.
+pub fn migrate_legacy_store(vault_name: &str) -> Result<(), Box<dyn Error>> {
+    let path = store_path(vault_name)?;
+    let legacy = config_dir()?.join(format!("{}.bin", store_name(vault_name)));
+    if path.exists() || !legacy.is_file() {
+        return Ok(());
+    }
+    if fs::rename(&legacy, &path).is_err() {
+        // The data directory may be on another file system.
+        fs::copy(&legacy, &path)?;
+        fs::remove_file(&legacy)?;
+    }
+    status!(
+        "Moved the vector store of vault '{}' from {} to {}",
+        vault_name,
+        legacy.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Deletes the vector store of `vault_name` along with its manifest and BM25
+/// index, as if the vault had never been vectorized.
+pub fn remove_vault_store(vault_name: &str) -> Result<(), Box<dyn Error>> {
+    for path in [
+        store_path(vault_name)?,
+        manifest_path(vault_name)?,
+        index_path(vault_name)?,
+    ] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the vector store of `vault_name` back to disk.
+pub async fn save_vault_store(vault_name: &str, store: &Store) -> Result<(), Box<dyn Error>> {
+    let dir = store_dir()?;
+    dump_store(&dir.to_string_lossy(), &store_name(vault_name), store)
+        .await
+        .map_err(|e| format!("Failed to dump store: {}", e))?;
+    Ok(())
+}
+
+/// Returns the ids of the vectors stored for the note at `relpath`: one per chunk,
+/// or the relpath itself in stores built before notes were chunked.
+pub fn note_ids(store: &Store, relpath: &str) -> Vec<String> {
+    let mut ids: Vec<String> = store
+        .id_to_index
+        .keys()
+        .filter(|id| note_of(id) == relpath)
+        .cloned()
+        .collect();
+    ids.sort_by_key(|id| parse_chunk_id(id).map(|(_, index)| index));
+    ids
+}
+
+/// Changes the ids of the vectors stored for the note at `old_relpath` to
+/// those of `new_relpath`. Returns false if the store has no vectors for it.
+pub fn rename_note(store: &mut Store, old_relpath: &str, new_relpath: &str) -> bool {
+    let ids = note_ids(store, old_relpath);
+    for id in &ids {
+        let new_id = rebase_id(id, new_relpath);
+        if let Some(index) = store.id_to_index.remove(id) {
+            store.id_to_index.insert(new_id.clone(), index);
+            store.index_to_id.insert(index, new_id);
+        }
+    }
+    !ids.is_empty()
+}
@@ -0,0 +1,374 @@
+// src/templates.rs
+
+use chrono::Local;
+use inquire::Text;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::yaml_scalar;
+use crate::frontmatter;
+use crate::paths::config_dir;
+use crate::picker::pick_one;
+use crate::vault::VaultContext;
+
+/// Directory inside a vault holding that vault's templates.
+pub const VAULT_TEMPLATE_DIR: &str = ".templates";
+
+/// A note template: a markdown file whose `{{variables}}` are filled in on use.
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+    /// True for templates from the vault's own `.templates` directory,
+    /// false for the global ones under the config directory.
+    pub in_vault: bool,
+}
+
+/// Values substituted for the built-in template variables.
+pub struct TemplateVars<'a> {
+    pub title: &'a str,
+    pub project: &'a str,
+    pub vault: &'a str,
+}
+
+/// Lists the templates available to `vault`: `<vault>/.templates/*.md` and
+/// `<config dir>/templates/*.md`. A vault template hides a global one of the same name.
+pub fn list_templates(vault: &VaultContext) -> Result<Vec<Template>, Box<dyn Error>> {
+    let mut templates: Vec<Template> = Vec::new();
+    let dirs = [
+        (vault.dir.join(VAULT_TEMPLATE_DIR), true),
+        (config_dir()?.join("templates"), false),
+    ];
+    for (dir, in_vault) in dirs {
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut found = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if !templates.iter().any(|t| t.name == name) {
+                found.push(Template {
+                    name,
+                    path,
+                    in_vault,
+                });
+            }
+        }
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        templates.extend(found);
+    }
+    Ok(templates)
+}
+
+/// Finds the template called `name`, or lets the user choose one in the
+/// picker when `name` is empty.
+pub fn select_template(vault: &VaultContext, name: &str) -> Result<Template, Box<dyn Error>> {
+    let templates = list_templates(vault)?;
+    if templates.is_empty() {
+        return Err(format!(
+            "No templates found; add some to {} or {}",
+            vault.dir.join(VAULT_TEMPLATE_DIR).display(),
+            config_dir()?.join("templates").display()
+        )
+        .into());
+    }
+
+    if !name.is_empty() {
+        return templates
+            .into_iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| format!("Template '{}' not found", name).into());
+    }
+
+    let label = |t: &Template| {
+        let scope = if t.in_vault { "vault" } else { "global" };
+        format!("{} ({})", t.name, scope)
+    };
+    let choices = templates.iter().map(label).collect();
+    let selected = pick_one(choices)?.ok_or("No template selected")?;
+    templates
+        .into_iter()
+        .find(|t| label(t) == selected)
+        .ok_or_else(|| "Invalid template selection".into())
+}
+
+/// Renders `template`, replacing each `{{name}}` with its value.
+///
+/// Built-in variables are `title`, `project`, `vault`, `date` (YYYY-MM-DD),
+/// `time` (HH:MM) and `datetime`; the date variables accept a chrono format,
+/// as in `{{date:%A, %d %B}}`. Any other variable is a custom field that is
+/// prompted for once when `interactive` is set, and is an error otherwise.
+///
+/// Values landing in the template's frontmatter are escaped for the scalar they
+/// land in, so that a title such as `C++: intro` keeps the frontmatter valid. A
+/// value in the middle of an unquoted one can't be escaped; if it breaks the
+/// frontmatter, rendering fails.
+pub fn render(
+    template: &str,
+    vars: &TemplateVars,
+    interactive: bool,
+) -> Result<String, Box<dyn Error>> {
+    let now = Local::now();
+    let mut custom: HashMap<String, String> = HashMap::new();
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    // Offset in `template` where the frontmatter block ends, if there is one.
+    let frontmatter_end = frontmatter::split(template)
+        .0
+        .map_or(0, |fm| "---\n".len() + fm.len());
+
+    while let Some(start) = rest.find("{{") {
+        let in_frontmatter = template.len() - rest.len() + start < frontmatter_end;
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let inner = rest[start + 2..start + len].trim();
+        rest = &rest[start + len + 2..];
+
+        let (key, format) = match inner.split_once(':') {
+            Some((key, format)) => (key.trim(), Some(format.trim())),
+            None => (inner, None),
+        };
+        let value = match key {
+            "title" => vars.title.to_string(),
+            "project" => vars.project.to_string(),
+            "vault" => vars.vault.to_string(),
+            "date" | "time" | "datetime" => {
+                let default = match key {
+                    "date" => "%Y-%m-%d",
+                    "time" => "%H:%M",
+                    _ => "%Y-%m-%d %H:%M",
+                };
+                let format = format.unwrap_or(default);
+                try_format(now.format(format))
+                    .map_err(|_| format!("Invalid date format '{}' in template", format))?
+            }
+            _ => match custom.get(key) {
+                Some(value) => value.clone(),
+                None if interactive => {
+                    let value = Text::new(&format!("{}:", key)).prompt()?;
+                    custom.insert(key.to_string(), value.clone());
+                    value
+                }
+                None => {
+                    return Err(format!(
+                        "Template field '{}' needs a value; run interactively to fill it in",
+                        key
+                    )
+                    .into());
+                }
+            },
+        };
+        if in_frontmatter {
+            push_yaml(&mut out, &value);
+        } else {
+            out.push_str(&value);
+        }
+    }
+    out.push_str(rest);
+
+    if let Some(fm) = frontmatter::split(&out).0
+        && let Err(e) = serde_yaml::from_str::<serde_yaml::Value>(fm)
+    {
+        return Err(format!(
+            "The template renders to invalid frontmatter ({}); quote the values \
+             that contain variables, as in title: \"Notes on {{{{title}}}}\"",
+            e
+        )
+        .into());
+    }
+    Ok(out)
+}
+
+/// Appends `value` to `out`, which ends inside a frontmatter line: escaped for
+/// the quoted string the template opened, as a YAML scalar where a value
+/// starts, or as is in the middle of a plain value.
+fn push_yaml(out: &mut String, value: &str) {
+    let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+    match scalar_at(line) {
+        Scalar::Double => out.push_str(&value.replace('\\', "\\\\").replace('"', "\\\"")),
+        Scalar::Single => out.push_str(&value.replace('\'', "''")),
+        Scalar::Start => out.push_str(&yaml_scalar(value)),
+        Scalar::Plain => out.push_str(value),
+    }
+}
+
+/// Where the end of a frontmatter line is, as far as YAML scalars go.
+#[derive(Debug, PartialEq, Eq)]
+enum Scalar {
+    /// Where a value starts: after `key: `, `- ` or at the start of the line.
+    Start,
+    /// Inside an unquoted value.
+    Plain,
+    /// Inside a double-quoted string.
+    Double,
+    /// Inside a single-quoted string.
+    Single,
+}
+
+/// Scans the frontmatter line `line` up to its end.
+fn scalar_at(line: &str) -> Scalar {
+    let mut state = Scalar::Start;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let at_space = chars.peek().is_some_and(|next| next.is_whitespace());
+        state = match (state, c) {
+            (Scalar::Double, '\\') => {
+                chars.next();
+                Scalar::Double
+            }
+            (Scalar::Double, '"') => Scalar::Plain,
+            (Scalar::Single, '\'') if chars.peek() == Some(&'\'') => {
+                chars.next();
+                Scalar::Single
+            }
+            (Scalar::Single, '\'') => Scalar::Plain,
+            (state @ (Scalar::Double | Scalar::Single), _) => state,
+            (Scalar::Start, '"') => Scalar::Double,
+            (Scalar::Start, '\'') => Scalar::Single,
+            (Scalar::Start, '-') if at_space => Scalar::Start,
+            (_, ':') if at_space => Scalar::Start,
+            (Scalar::Start, c) if c.is_whitespace() => Scalar::Start,
+            _ => Scalar::Plain,
+        };
+    }
+    state
+}
+
+/// Combines a note skeleton from `note_skeleton` with a rendered template:
+/// a template with its own frontmatter replaces the skeleton, any other
+/// template is appended to it.
+pub fn apply(skeleton: String, rendered: &str) -> String {
+    if frontmatter::split(rendered).0.is_some() {
+        rendered.to_string()
+    } else {
+        skeleton + rendered
+    }
+}
+
+/// Formats a chrono `DelayedFormat` into a string, returning an error for an
+/// invalid format string where `to_string` would panic.
+pub fn try_format(formatted: impl Display) -> Result<String, fmt::Error> {
+    let mut out = String::new();
+    write!(out, "{}", formatted)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: TemplateVars = TemplateVars {
+        title: "C++: intro #planning",
+        project: "work",
+        vault: "main",
+    };
+
+    #[test]
+    fn render_quotes_frontmatter_values() {
+        let template = "---\ntitle: {{title}}\nproject: {{project}}\n---\n# {{title}}\n";
+        let rendered = render(template, &VARS, false).unwrap();
+        assert_eq!(
+            rendered,
+            "---\ntitle: \"C++: intro #planning\"\nproject: work\n---\n# C++: intro #planning\n"
+        );
+        let fm = frontmatter::split(&rendered).0.unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(fm).unwrap();
+        assert_eq!(value["title"].as_str(), Some(VARS.title));
+    }
+
+    #[test]
+    fn render_escapes_values_in_quoted_frontmatter_strings() {
+        let vars = TemplateVars {
+            title: "Say \"hi\": it's #1",
+            ..VARS
+        };
+        let template = "---\na: \"{{title}}\"\nb: '{{title}}'\n---\n";
+        let rendered = render(template, &vars, false).unwrap();
+        let value: serde_yaml::Value =
+            serde_yaml::from_str(frontmatter::split(&rendered).0.unwrap()).unwrap();
+        assert_eq!(value["a"].as_str(), Some(vars.title));
+        assert_eq!(value["b"].as_str(), Some(vars.title));
+    }
+
+    #[test]
+    fn render_leaves_templates_without_frontmatter_raw() {
+        let rendered = render("Notes on {{title}}", &VARS, false).unwrap();
+        assert_eq!(rendered, "Notes on C++: intro #planning");
+    }
+
+    fn field(rendered: &str, key: &str) -> Option<String> {
+        let fm = frontmatter::split(rendered).0.unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(fm).unwrap();
+        value[key].as_str().map(str::to_string)
+    }
+
+    #[test]
+    fn render_escapes_values_in_the_middle_of_quoted_strings() {
+        let vars = TemplateVars {
+            title: "Say \"hi\": it's #1 \\o/",
+            ..VARS
+        };
+        let template = "---\na: \"Meeting: {{title}} ({{project}})\"\nb: 'It''s {{title}}'\n---\n";
+        let rendered = render(template, &vars, false).unwrap();
+        assert_eq!(
+            field(&rendered, "a").as_deref(),
+            Some("Meeting: Say \"hi\": it's #1 \\o/ (work)")
+        );
+        assert_eq!(
+            field(&rendered, "b").as_deref(),
+            Some("It's Say \"hi\": it's #1 \\o/")
+        );
+    }
+
+    #[test]
+    fn render_writes_values_in_the_middle_of_plain_values_as_is() {
+        let vars = TemplateVars {
+            title: "Weekly sync",
+            ..VARS
+        };
+        let template = "---\ntitle: Notes on {{title}}\ntags:\n  - {{title}}\n---\n";
+        let rendered = render(template, &vars, false).unwrap();
+        assert_eq!(
+            field(&rendered, "title").as_deref(),
+            Some("Notes on Weekly sync")
+        );
+
+        let vars = TemplateVars {
+            title: "#1: \"intro\"",
+            ..VARS
+        };
+        let rendered = render("---\ntags:\n  - {{title}}\n---\n", &vars, false).unwrap();
+        let fm = frontmatter::split(&rendered).0.unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(fm).unwrap();
+        assert_eq!(value["tags"][0].as_str(), Some(vars.title));
+    }
+
+    #[test]
+    fn render_fails_on_values_that_break_plain_frontmatter() {
+        let err = render("---\ntitle: Notes on {{title}}\n---\n", &VARS, false).unwrap_err();
+        assert!(err.to_string().contains("invalid frontmatter"));
+    }
+
+    #[test]
+    fn scalar_at_tracks_quotes_and_value_starts() {
+        assert_eq!(scalar_at("title: "), Scalar::Start);
+        assert_eq!(scalar_at("  - "), Scalar::Start);
+        assert_eq!(scalar_at("title: Notes "), Scalar::Plain);
+        assert_eq!(scalar_at("title: it's "), Scalar::Plain);
+        assert_eq!(scalar_at("title: \"a: \\\" "), Scalar::Double);
+        assert_eq!(scalar_at("title: \"done\" "), Scalar::Plain);
+        assert_eq!(scalar_at("title: 'it''s "), Scalar::Single);
+        assert_eq!(scalar_at("url: http://x "), Scalar::Plain);
+        assert_eq!(scalar_at("time: 10:"), Scalar::Plain);
+    }
+}
@@ -0,0 +1,218 @@
+// src/trash.rs
+
+use chrono::{DateTime, Local, SecondsFormat};
+use inquire::Confirm;
+use notemancy_core::utils::NoteInfo;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use crate::bm25::Bm25Index;
+use crate::crud::write_new_note;
+use crate::notes::backlinks;
+use crate::store::{load_vault_store, note_ids, save_vault_store, store_name};
+use crate::vault::VaultContext;
+
+/// Directory inside a vault that deleted notes are moved to.
+pub const TRASH_DIR: &str = ".trash";
+
+/// Metadata kept next to each trashed note as `<id>.yaml`.
+#[derive(Serialize, Deserialize)]
+struct TrashMeta {
+    /// Path of the note relative to the vault before it was deleted.
+    original: String,
+    /// When the note was deleted, in RFC 3339 format.
+    deleted: String,
+}
+
+/// A note in the trash. Its id is the file stem shared by `<id>.md` and `<id>.yaml`.
+struct TrashEntry {
+    id: String,
+    meta: TrashMeta,
+}
+
+fn trash_dir(vault: &VaultContext) -> PathBuf {
+    vault.dir.join(TRASH_DIR)
+}
+
+/// Writes `content` to `dir` as `<base>.md` and returns `base` as its trash id.
+/// If a note with the same file name was trashed in the same second, the id
+/// gets a numeric suffix instead, as in `<base>-2`.
+fn write_trashed(dir: &Path, base: &str, content: &str) -> Result<String, Box<dyn Error>> {
+    let mut n = 1;
+    loop {
+        let id = if n == 1 {
+            base.to_string()
+        } else {
+            format!("{}-{}", base, n)
+        };
+        match write_new_note(&dir.join(format!("{}.md", id)), content) {
+            Ok(()) => return Ok(id),
+            Err(e)
+                if e.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::AlreadyExists) =>
+            {
+                n += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Implements `ncy rm`: moves `note` to the vault's `.trash` directory.
+///
+/// The note is dropped from the vault's vector store. Notes that link to it are
+/// listed, since those links break until the note is restored.
+pub async fn trash_note(vault: &VaultContext, note: &NoteInfo) -> Result<(), Box<dyn Error>> {
+    let linking = backlinks(vault, note)?;
+
+    let now = Local::now();
+    let stem = Path::new(&note.relpath)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir = trash_dir(vault);
+    let meta = TrashMeta {
+        original: note.relpath.clone(),
+        deleted: now.to_rfc3339_opts(SecondsFormat::Secs, true),
+    };
+
+    let source = vault.dir.join(&note.relpath);
+    let base = format!("{}-{}", now.format("%Y%m%dT%H%M%S"), stem);
+    let id = write_trashed(&dir, &base, &fs::read_to_string(&source)?)?;
+    fs::write(
+        dir.join(format!("{}.yaml", id)),
+        serde_yaml::to_string(&meta)?,
+    )?;
+    fs::remove_file(&source)?;
+    status!("Moved {} to the trash as '{}'", note.relpath, id);
+
+    if let Some(mut store) = load_vault_store(&vault.name)? {
+        let ids = note_ids(&store, &note.relpath);
+        if !ids.is_empty() {
+            store
+                .delete_vectors(&ids, &store_name(&vault.name))
+                .map_err(|e| format!("Failed to update vector store: {}", e))?;
+            save_vault_store(&vault.name, &store).await?;
+        }
+    }
+    if let Some(mut index) = Bm25Index::load(&vault.name)? {
+        index.remove_note(&note.relpath);
+        index.save(&vault.name)?;
+    }
+
+    if !linking.is_empty() {
+        eprintln!(
+            "Warning: {} note(s) link to {} and now have broken links:",
+            linking.len(),
+            note.relpath
+        );
+        for relpath in &linking {
+            eprintln!("  {}", relpath);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the metadata of every note in the trash, oldest first.
+fn entries(vault: &VaultContext) -> Result<Vec<TrashEntry>, Box<dyn Error>> {
+    let dir = trash_dir(vault);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "yaml") {
+            continue;
+        }
+        let id = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let meta: TrashMeta = serde_yaml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| format!("Invalid trash metadata in {}: {}", path.display(), e))?;
+        entries.push(TrashEntry { id, meta });
+    }
+    entries.sort_by(|a, b| a.meta.deleted.cmp(&b.meta.deleted));
+    Ok(entries)
+}
+
+/// Implements `ncy trash list`.
+pub fn list_trash(vault: &VaultContext) -> Result<(), Box<dyn Error>> {
+    let entries = entries(vault)?;
+    if entries.is_empty() {
+        status!("The trash of vault '{}' is empty", vault.name);
+        return Ok(());
+    }
+    for entry in entries {
+        let deleted = DateTime::parse_from_rfc3339(&entry.meta.deleted)
+            .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or(entry.meta.deleted);
+        println!("{}  {}  {}", entry.id, deleted, entry.meta.original);
+    }
+    Ok(())
+}
+
+/// Implements `ncy trash restore`: moves a trashed note back to its original
+/// path. `query` is a trash id or an original path; for a path deleted more than
+/// once the most recent copy is restored.
+pub fn restore_note(vault: &VaultContext, query: &str) -> Result<(), Box<dyn Error>> {
+    let entry = entries(vault)?
+        .into_iter()
+        .rev()
+        .find(|entry| entry.id == query || entry.meta.original == query)
+        .ok_or_else(|| format!("No note '{}' in the trash of vault '{}'", query, vault.name))?;
+
+    let dir = trash_dir(vault);
+    let trashed = dir.join(format!("{}.md", entry.id));
+    let target = vault.dir.join(&entry.meta.original);
+    if target.exists() {
+        return Err(format!(
+            "Cannot restore '{}': a note already exists at {}",
+            entry.id,
+            target.display()
+        )
+        .into());
+    }
+    write_new_note(&target, &fs::read_to_string(&trashed)?)?;
+    fs::remove_file(&trashed)?;
+    fs::remove_file(dir.join(format!("{}.yaml", entry.id)))?;
+
+    status!(
+        "Restored {}; run 'ncy vectorize' to index it again",
+        entry.meta.original
+    );
+    println!("{}", target.display());
+    Ok(())
+}
+
+/// Implements `ncy trash empty`: permanently deletes every note in the trash,
+/// after confirmation unless `yes` is set.
+pub fn empty_trash(vault: &VaultContext, yes: bool) -> Result<(), Box<dyn Error>> {
+    let entries = entries(vault)?;
+    if entries.is_empty() {
+        status!("The trash of vault '{}' is already empty", vault.name);
+        return Ok(());
+    }
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err("Refusing to empty the trash without confirmation; use --yes".into());
+        }
+        let confirmed = Confirm::new(&format!(
+            "Permanently delete {} note(s) from the trash of '{}'?",
+            entries.len(),
+            vault.name
+        ))
+        .with_default(false)
+        .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+    }
+    fs::remove_dir_all(trash_dir(vault))?;
+    status!("Deleted {} note(s) from the trash", entries.len());
+    Ok(())
+}
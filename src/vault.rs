@@ -0,0 +1,339 @@
+// src/vault.rs
+
+use crate::config::{ConfigDocument, clear_default_vault, read_default_vault};
+use crate::store::{index_path, manifest_path, migrate_legacy_store, store_path};
+use notemancy_core::config::{get_vault_dir, read_config};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory marker file that pins a vault for a directory tree.
+pub const MARKER_FILE: &str = ".ncy";
+
+/// Environment variable that selects the vault when no `--vault` flag is given.
+pub const VAULT_ENV: &str = "NCY_VAULT";
+
+/// Vault name that stands for every configured vault, written `@*` on the command line.
+pub const ALL_VAULTS: &str = "*";
+
+/// Where the active vault name came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VaultSource {
+    /// The `--vault` flag or an `@vault` argument.
+    Flag,
+    /// The `NCY_VAULT` environment variable.
+    Env,
+    /// A `.ncy` marker file, or the current directory being inside a vault directory.
+    Directory(PathBuf),
+    /// `default_vault.txt` in the configuration directory, as written by `ncy set`.
+    DefaultFile,
+    /// The `default_vault` key in config.yaml, or the first configured vault.
+    Config,
+}
+
+impl fmt::Display for VaultSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultSource::Flag => write!(f, "command line"),
+            VaultSource::Env => write!(f, "{}", VAULT_ENV),
+            VaultSource::Directory(path) => write!(f, "{}", path.display()),
+            VaultSource::DefaultFile => write!(f, "default_vault.txt"),
+            VaultSource::Config => write!(f, "config.yaml"),
+        }
+    }
+}
+
+/// The vault a command operates on.
+#[derive(Debug, Clone)]
+pub struct VaultContext {
+    /// Vault name as defined in config.yaml.
+    pub name: String,
+    /// Absolute vault directory.
+    pub dir: PathBuf,
+    /// Where `name` came from.
+    pub source: VaultSource,
+}
+
+/// Resolves the active vault.
+///
+/// The first of the following that yields a vault name wins:
+///
+/// 1. `flag` (the `--vault` option or an `@vault` argument),
+/// 2. the `NCY_VAULT` environment variable,
+/// 3. the nearest `.ncy` marker file, or configured vault directory, containing the
+///    current directory,
+/// 4. `default_vault.txt` in the configuration directory,
+/// 5. the `default_vault` key in config.yaml, falling back to the first configured vault.
+///
+/// # Errors
+///
+/// Returns an error if no vault can be determined or the chosen vault is not
+/// defined in config.yaml.
+pub fn resolve_vault(flag: Option<&str>) -> Result<VaultContext, Box<dyn Error>> {
+    let (name, source) = resolve_name(flag)?;
+    if name == ALL_VAULTS {
+        return Err(format!(
+            "'@{}' (selected by {}) is only supported by the note picker and 'ncy edit'",
+            ALL_VAULTS, source
+        )
+        .into());
+    }
+    vault_context(name, source)
+}
+
+/// Resolves the vaults for commands that can work across vaults: every
+/// configured vault when `all` is set or the vault resolves to `@*`, otherwise
+/// the single vault chosen by `resolve_vault`.
+pub fn resolve_vaults(flag: Option<&str>, all: bool) -> Result<Vec<VaultContext>, Box<dyn Error>> {
+    let (name, source) = if all {
+        (ALL_VAULTS.to_string(), VaultSource::Flag)
+    } else {
+        resolve_name(flag)?
+    };
+    if name != ALL_VAULTS {
+        return Ok(vec![vault_context(name, source)?]);
+    }
+    let vaults: Vec<VaultContext> = configured_vaults()?
+        .into_iter()
+        .map(|(name, dir)| VaultContext {
+            name,
+            dir,
+            source: source.clone(),
+        })
+        .collect();
+    if vaults.is_empty() {
+        return Err("No vaults configured; add one with 'ncy vault add <name> <path>'".into());
+    }
+    Ok(vaults)
+}
+
+fn vault_context(name: String, source: VaultSource) -> Result<VaultContext, Box<dyn Error>> {
+    let dir = get_vault_dir(&name).map_err(|e| format!("{} (vault selected by {})", e, source))?;
+    Ok(VaultContext {
+        name,
+        dir: PathBuf::from(dir),
+        source,
+    })
+}
+
+fn resolve_name(flag: Option<&str>) -> Result<(String, VaultSource), Box<dyn Error>> {
+    if let Some(name) = flag.and_then(non_empty) {
+        return Ok((name, VaultSource::Flag));
+    }
+
+    if let Some(name) = env::var(VAULT_ENV).ok().as_deref().and_then(non_empty) {
+        return Ok((name, VaultSource::Env));
+    }
+
+    if let Ok(cwd) = env::current_dir()
+        && let Some((name, path)) = vault_for_directory(&cwd)?
+    {
+        return Ok((name, VaultSource::Directory(path)));
+    }
+
+    if let Ok(Some(name)) = read_default_vault() {
+        return Ok((name, VaultSource::DefaultFile));
+    }
+
+    let config = read_config()?;
+    let configured = config
+        .get("default_vault")
+        .and_then(|v| v.as_str())
+        .and_then(non_empty)
+        .or_else(|| {
+            config
+                .get("vaults")
+                .and_then(|v| v.as_array())
+                .and_then(|vaults| vaults.first())
+                .and_then(|v| v.get("name"))
+                .and_then(|n| n.as_str())
+                .and_then(non_empty)
+        });
+    match configured {
+        Some(name) => Ok((name, VaultSource::Config)),
+        None => Err(
+            "No vault configured; add one to config.yaml or set a default with 'ncy set <vault_name>'"
+                .into(),
+        ),
+    }
+}
+
+/// Walks up from `start` looking for a `.ncy` marker file or a configured vault directory.
+/// Returns the vault name and the file or directory it was found in.
+fn vault_for_directory(start: &Path) -> Result<Option<(String, PathBuf)>, Box<dyn Error>> {
+    // A missing or invalid config is reported later by `resolve_vault`.
+    let vault_dirs: Vec<(String, PathBuf)> = configured_vaults()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, dir)| (name, dir.canonicalize().unwrap_or(dir)))
+        .collect();
+    for dir in start.ancestors() {
+        let marker = dir.join(MARKER_FILE);
+        if marker.is_file()
+            && let Some(name) = non_empty(&fs::read_to_string(&marker)?)
+        {
+            return Ok(Some((name, marker)));
+        }
+        let canonical = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        if let Some((name, _)) = vault_dirs.iter().find(|(_, d)| *d == canonical) {
+            return Ok(Some((name.clone(), dir.to_path_buf())));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns every vault in config.yaml as (name, directory), in file order.
+pub fn configured_vaults() -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let config = read_config()?;
+    let vaults = config
+        .get("vaults")
+        .and_then(|v| v.as_array())
+        .ok_or("Invalid configuration: 'vaults' key is missing or not an array")?;
+    Ok(vaults
+        .iter()
+        .filter_map(|v| {
+            let name = v.get("name")?.as_str()?;
+            let dir = v.get("directory")?.as_str()?;
+            Some((name.to_string(), PathBuf::from(dir)))
+        })
+        .collect())
+}
+
+/// Prints every configured vault with its directory.
+/// The vault that commands use when no `--vault` is given is marked with `*`.
+pub fn list_vaults() -> Result<(), Box<dyn Error>> {
+    let vaults = configured_vaults()?;
+    if vaults.is_empty() {
+        status!("No vaults configured; add one with 'ncy vault add <name> <path>'");
+        return Ok(());
+    }
+    let active = resolve_name(None).ok().map(|(name, _)| name);
+    let width = vaults.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, dir) in vaults {
+        let marker = if active.as_deref() == Some(name.as_str()) {
+            '*'
+        } else {
+            ' '
+        };
+        let missing = if dir.is_dir() { "" } else { "  (missing)" };
+        println!(
+            "{} {:width$}  {}{}",
+            marker,
+            name,
+            dir.display(),
+            missing,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// Adds a vault named `name` for the existing directory `path` to config.yaml.
+pub fn add_vault(name: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    validate_vault_name(name)?;
+    if !path.is_dir() {
+        return Err(format!("Directory '{}' does not exist", path.display()).into());
+    }
+    let directory = path.canonicalize()?;
+
+    let mut doc = ConfigDocument::load()?;
+    doc.add_vault(name, &directory)?;
+    doc.save()?;
+    status!("Added vault '{}' at {}", name, directory.display());
+    Ok(())
+}
+
+/// Removes the vault `name` from config.yaml. The vault directory itself is left untouched.
+pub fn remove_vault(name: &str) -> Result<(), Box<dyn Error>> {
+    let mut doc = ConfigDocument::load()?;
+    doc.remove_vault(name)?;
+    let config_default = doc.default_vault().as_deref() == Some(name);
+    if config_default {
+        doc.set_default_vault(None);
+    }
+    doc.save()?;
+    if config_default {
+        status!(
+            "'{}' was the default_vault in config.yaml; the key has been removed",
+            name
+        );
+    }
+    if read_default_vault()?.as_deref() == Some(name) {
+        clear_default_vault()?;
+        status!(
+            "'{}' was the default vault; the default has been cleared",
+            name
+        );
+    }
+    status!("Removed vault '{}'", name);
+    Ok(())
+}
+
+/// Renames the vault `old_name` to `new_name`, carrying over the default vault
+/// setting and the vault's vector store, its manifest and its BM25 index.
+pub fn rename_vault(old_name: &str, new_name: &str) -> Result<(), Box<dyn Error>> {
+    validate_vault_name(new_name)?;
+    let mut doc = ConfigDocument::load()?;
+    doc.rename_vault(old_name, new_name)?;
+    if doc.default_vault().as_deref() == Some(old_name) {
+        doc.set_default_vault(Some(new_name));
+    }
+    doc.save()?;
+
+    if read_default_vault()?.as_deref() == Some(old_name) {
+        crate::config::set_default_vault(new_name)?;
+    }
+    migrate_legacy_store(old_name)?;
+    for path in [store_path, manifest_path, index_path] {
+        let old_path = path(old_name)?;
+        if old_path.exists() {
+            fs::rename(&old_path, path(new_name)?)?;
+        }
+    }
+    status!("Renamed vault '{}' to '{}'", old_name, new_name);
+    Ok(())
+}
+
+/// Prints the name, directory and note count of `vault`, and how it was selected.
+pub fn show_vault(vault: &VaultContext) -> Result<(), Box<dyn Error>> {
+    println!("name:        {}", vault.name);
+    println!("directory:   {}", vault.dir.display());
+    if vault.dir.is_dir() {
+        let notes = crate::notes::list_notes(&vault.name)?;
+        println!("notes:       {}", notes.len());
+    } else {
+        println!("notes:       directory is missing");
+    }
+    println!("selected by: {}", vault.source);
+    Ok(())
+}
+
+/// Vault names appear in `@vault` arguments and in file names, so they are
+/// restricted to letters, digits, '-', '_' and '.'.
+pub fn validate_vault_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid vault name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        )
+        .into())
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    let trimmed = s.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
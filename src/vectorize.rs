@@ -1,109 +1,300 @@
 // src/vectorize.rs
-use hddb::core::{create_store, dump_store};
+use crate::notes::list_notes;
+use hddb::core::{Store, create_store};
 use notemancy_core::ai::sentence_transformer::generate_embedding;
-use notemancy_core::crud::read_note;
-use notemancy_core::utils::list_notes;
-use std::env;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::bm25::Bm25Index;
+use crate::chunk::{Chunk, chunk_id, chunk_note};
+use crate::config::EmbeddingConfig;
+use crate::frontmatter;
+use crate::related::blank_section;
+use crate::store::{
+    Manifest, ManifestEntry, load_vault_store, note_ids, remove_vault_store, save_vault_store,
+    store_name, store_path,
+};
+use crate::vault::VaultContext;
 use tch::Tensor;
 
-/// Vectorizes all notes in a vault by generating embeddings using sentence transformers
+/// Hashes `text` with 64-bit FNV-1a, which unlike `DefaultHasher` is stable
+/// across Rust releases.
+fn content_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Vectorizes the notes in a vault by generating embeddings using sentence transformers
 /// and storing them in a vector store using hddb.
 ///
+/// Each note is split into heading-aware chunks (see `chunk::chunk_note`) and
+/// every chunk gets its own vector, stored under `chunk_id(relpath, i)`. Only
+/// notes that are new or whose text changed since the last run are embedded;
+/// notes that no longer exist are dropped from the store. Changes are detected
+/// with a manifest of content hashes and modification times kept next to the
+/// store, which also records where each chunk lies in its note. With `full`, or
+/// after the chunking settings change, every note is embedded again.
+///
+/// The chunks are also indexed for lexical search in a BM25 index kept next
+/// to the store (see `bm25::Bm25Index`).
+///
 /// # Parameters
 ///
-/// - `vault_name`: The name of the vault to vectorize.
+/// - `vault`: The vault to vectorize.
+/// - `full`: Re-embed every note, discarding the existing store.
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` if the vectorization is successful, otherwise returns an error.
-pub async fn vectorize_vault(vault_name: &str) -> Result<(), Box<dyn Error>> {
-    println!("Vectorizing notes in vault '{}'...", vault_name);
-
-    // Get the config directory for storing the vector store
-    let conf_dir = env::var("NOTEMANCY_CONF_DIR")
-        .map_err(|_| "Environment variable NOTEMANCY_CONF_DIR is not set")?;
-
-    // Create the store name
-    let store_name = format!("{}_vectors", vault_name);
+pub async fn vectorize_vault(vault: &VaultContext, full: bool) -> Result<(), Box<dyn Error>> {
+    let vault_name = vault.name.as_str();
+    status!("Vectorizing notes in vault '{}'...", vault_name);
+    let settings = EmbeddingConfig::load();
 
-    // Check if the vector store already exists and remove it
-    let store_path = Path::new(&conf_dir).join(format!("{}.bin", store_name));
-    if store_path.exists() {
-        println!("Removing existing vector store: {}", store_path.display());
-        fs::remove_file(&store_path)?;
+    // The manifest only describes an existing store.
+    let store = if full {
+        None
+    } else {
+        load_vault_store(vault_name)?
+    };
+    let mut old_manifest = match store {
+        Some(_) => Manifest::load(vault_name)?,
+        None => Manifest::default(),
+    };
+    if (old_manifest.chunk_size, old_manifest.chunk_overlap)
+        != (settings.chunk_size, settings.chunk_overlap)
+    {
+        if !old_manifest.notes.is_empty() {
+            status!("Chunking settings changed, embedding every note again");
+        }
+        old_manifest.notes.clear();
     }
+    let mut index = if old_manifest.notes.is_empty() {
+        Bm25Index::default()
+    } else {
+        Bm25Index::load(vault_name)?.unwrap_or_default()
+    };
+    let stored = |id: &str| {
+        store
+            .as_ref()
+            .is_some_and(|store| store.id_to_index.contains_key(id))
+    };
+    let all_stored =
+        |relpath: &str, count: usize| (0..count).all(|i| stored(&chunk_id(relpath, i)));
+    let all_indexed = |index: &Bm25Index, relpath: &str, count: usize| {
+        (0..count).all(|i| index.contains(&chunk_id(relpath, i)))
+    };
 
     // Get all notes from the vault
     let notes = list_notes(vault_name)?;
-    if notes.is_empty() {
-        println!("No notes found in vault '{}'", vault_name);
-        return Ok(());
-    }
-
-    println!("Found {} notes", notes.len());
+    status!("Found {} notes", notes.len());
 
-    // Prepare vectors for storing note data and embeddings
-    let mut note_ids: Vec<String> = Vec::with_capacity(notes.len());
-    let mut embeddings: Vec<Tensor> = Vec::with_capacity(notes.len());
+    let mut manifest = Manifest {
+        chunk_size: settings.chunk_size,
+        chunk_overlap: settings.chunk_overlap,
+        ..Manifest::default()
+    };
+    // Ids of every vector the store should hold afterwards.
+    let mut current: HashSet<String> = HashSet::new();
+    // Ids of the indexed chunks that are still current, and the chunks to
+    // index afresh.
+    let mut still_indexed: HashSet<String> = HashSet::new();
+    let mut to_index: Vec<(String, String)> = Vec::new();
+    let mut added: Vec<(String, Tensor)> = Vec::new();
+    let mut updated: Vec<(String, Tensor)> = Vec::new();
+    let mut embedded = 0;
+    let mut unchanged = 0;
 
     // Process each note
-    for note in notes {
-        println!("Processing note: {}", note.relpath);
+    for note in &notes {
+        let path = vault.dir.join(&note.relpath);
+        let mtime = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_nanos() as u64);
+        let previous = old_manifest.notes.get(&note.relpath);
+        if let Some(previous) = previous
+            && previous.mtime == mtime
+            && all_stored(&note.relpath, previous.chunks.len())
+            && all_indexed(&index, &note.relpath, previous.chunks.len())
+        {
+            let ids = (0..previous.chunks.len()).map(|i| chunk_id(&note.relpath, i));
+            current.extend(ids.clone());
+            still_indexed.extend(ids);
+            manifest
+                .notes
+                .insert(note.relpath.clone(), previous.clone());
+            unchanged += 1;
+            continue;
+        }
+
+        // Hash the body only, but chunk the whole file so that chunk line
+        // numbers match what an editor shows. The related notes section is
+        // left out of both, so writing it does not make the note look changed.
+        let content = fs::read_to_string(&path)?;
+        let content = blank_section(&content);
+        let hash = content_hash(frontmatter::split(&content).1.trim_end());
+        let chunks = chunk_note(&content, settings.chunk_size, settings.chunk_overlap);
+        let entry = ManifestEntry {
+            hash,
+            mtime,
+            chunks: chunks.iter().map(|chunk| chunk.meta.clone()).collect(),
+        };
+        // The index follows the text even if embedding fails below.
+        to_index.extend(
+            chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| (chunk_id(&note.relpath, i), chunk.text.clone())),
+        );
+        if let Some(previous) = previous
+            && previous.hash == entry.hash
+            && previous.chunks.len() == chunks.len()
+            && all_stored(&note.relpath, chunks.len())
+        {
+            current.extend((0..chunks.len()).map(|i| chunk_id(&note.relpath, i)));
+            manifest.notes.insert(note.relpath.clone(), entry);
+            unchanged += 1;
+            continue;
+        }
 
-        // Read the note content without frontmatter
-        let content = read_note(vault_name, &note.relpath, false)?;
+        status!(
+            "Processing note: {} ({} chunks)",
+            note.relpath,
+            chunks.len()
+        );
 
-        // Generate embedding for the note content
-        let note_embeddings = generate_embedding(&content)?;
+        let vectors = match embed_chunks(&note.relpath, &chunks) {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                // Keep the note's old vectors; without a manifest entry it is
+                // embedded again on the next run.
+                eprintln!("  Warning: Failed to embed note {}: {}", note.relpath, e);
+                if let Some(store) = &store {
+                    current.extend(note_ids(store, &note.relpath));
+                }
+                continue;
+            }
+        };
 
-        if note_embeddings.is_empty() {
-            println!(
-                "  Warning: No embedding generated for note {}",
-                note.relpath
-            );
-            continue;
+        for (id, tensor) in vectors {
+            current.insert(id.clone());
+            if stored(&id) {
+                updated.push((id, tensor));
+            } else {
+                added.push((id, tensor));
+            }
         }
+        manifest.notes.insert(note.relpath.clone(), entry);
+        embedded += 1;
+    }
 
-        // The embedding should be a single vector for the entire note
-        let embedding = &note_embeddings[0];
+    // Drop the vectors of notes that were deleted or moved away, of chunks
+    // past the end of shortened notes, and ids of stores built before notes
+    // were chunked.
+    let removed: Vec<String> = store
+        .as_ref()
+        .map(|store| {
+            store
+                .id_to_index
+                .keys()
+                .filter(|id| !current.contains(id.as_str()))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
 
-        // Convert to tensor
-        let tensor = Tensor::f_from_slice(embedding)
-            .map_err(|e| format!("Failed to create tensor: {}", e))?;
+    index.remove(|id| !still_indexed.contains(id));
+    for (id, text) in &to_index {
+        index.insert(id, text);
+    }
+    index.save(vault_name)?;
 
-        // Store the note ID and embedding
-        note_ids.push(note.relpath.clone());
-        embeddings.push(tensor);
+    // Without a store to update, nothing embedded means no store at all; a
+    // full run must not leave the previous one behind for search to return
+    // notes that are gone.
+    if store.is_none() && added.is_empty() {
+        remove_vault_store(vault_name)?;
+        status!("No embeddings were generated");
+        return Ok(());
     }
 
-    if embeddings.is_empty() {
-        println!("No embeddings were generated");
+    if added.is_empty() && updated.is_empty() && removed.is_empty() {
+        manifest.save(vault_name)?;
+        status!("Vector store is up to date ({} notes)", unchanged);
         return Ok(());
     }
 
-    // Stack all embeddings into a single tensor
-    let stacked_embeddings = Tensor::stack(&embeddings, 0);
+    let name = store_name(vault_name);
+    let store = match store {
+        Some(mut store) => {
+            let failed = |e| format!("Failed to update vector store: {}", e);
+            if !removed.is_empty() {
+                store.delete_vectors(&removed, &name).map_err(failed)?;
+            }
+            if !updated.is_empty() {
+                store.update_vectors(&updated, &name).map_err(failed)?;
+            }
+            if !added.is_empty() {
+                store.add_vectors(&added, &name).map_err(failed)?;
+            }
+            store
+        }
+        None => new_store(&added),
+    };
 
-    // Create a store with the embeddings
-    let mut store = create_store(stacked_embeddings);
+    // Save the vector store and the manifest describing it
+    save_vault_store(vault_name, &store).await?;
+    manifest.save(vault_name)?;
 
-    // Update store IDs to match note relpaths
-    for (i, id) in note_ids.iter().enumerate() {
-        store.index_to_id.insert(i, id.clone());
-        store.id_to_index.insert(id.clone(), i);
-    }
+    status!(
+        "Embedded {} notes: added {}, updated {} and removed {} chunks ({} notes unchanged)",
+        embedded,
+        added.len(),
+        updated.len(),
+        removed.len(),
+        unchanged
+    );
+    status!("Location: {}", store_path(vault_name)?.display());
 
-    // Save the vector store - explicitly handle the error
-    match dump_store(&conf_dir, &store_name, &store).await {
-        Ok(_) => {}
-        Err(e) => return Err(format!("Failed to dump store: {}", e).into()),
+    Ok(())
+}
+
+/// Embeds each chunk of the note at `relpath`, returning the vectors keyed by
+/// chunk id. Fails if any chunk cannot be embedded.
+fn embed_chunks(relpath: &str, chunks: &[Chunk]) -> Result<Vec<(String, Tensor)>, Box<dyn Error>> {
+    let mut vectors = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let embeddings = generate_embedding(&chunk.text)?;
+        let Some(embedding) = embeddings.first() else {
+            return Err(format!("no embedding generated for chunk {}", i).into());
+        };
+        let tensor = Tensor::f_from_slice(embedding)
+            .map_err(|e| format!("Failed to create tensor: {}", e))?;
+        vectors.push((chunk_id(relpath, i), tensor));
     }
+    Ok(vectors)
+}
 
-    println!("Vector store created and saved as '{}'", store_name);
-    println!("Location: {}", store_path.display());
+/// Creates a store holding `vectors`, keyed by their ids.
+fn new_store(vectors: &[(String, Tensor)]) -> Store {
+    // Stack all embeddings into a single tensor
+    let embeddings: Vec<&Tensor> = vectors.iter().map(|(_, tensor)| tensor).collect();
+    let mut store = create_store(Tensor::stack(&embeddings, 0));
 
-    Ok(())
+    // Update store IDs to match the chunk ids
+    store.index_to_id.clear();
+    store.id_to_index.clear();
+    for (i, (id, _)) in vectors.iter().enumerate() {
+        store.index_to_id.insert(i, id.clone());
+        store.id_to_index.insert(id.clone(), i);
+    }
+    store
 }